@@ -0,0 +1,26 @@
+// Generates the C header from `src/capi.rs` when the `capi` feature is
+// enabled, and writes it to `OUT_DIR`. The `capi` test checks that the
+// committed `include/slop.h` matches it. (See `cbindgen.toml`)
+fn main() {
+    #[cfg(feature = "capi")]
+    {
+        use std::{env, path::Path};
+
+        println!("cargo:rerun-if-changed=src/capi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+
+        let dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let dir = Path::new(&dir);
+        let config = cbindgen::Config::from_file(dir.join("cbindgen.toml"))
+            .expect("couldn't read cbindgen.toml");
+
+        // Only the `capi` module is parsed, so nothing else ends up in the
+        // header.
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(dir.join("src/capi.rs"))
+            .generate()
+            .expect("couldn't generate the c header")
+            .write_to_file(Path::new(&env::var("OUT_DIR").unwrap()).join("slop.h"));
+    }
+}
@@ -0,0 +1,204 @@
+/*
+ * C bindings for slop-rs. (See the `capi` module of the crate)
+ *
+ * Ownership:
+ *
+ * - A `Slop *` is owned by the caller, and must be freed exactly once with
+ *   `slop_free`.
+ * - Every `char *` returned by the API is a new copy owned by the caller, and
+ *   must be freed with `slop_string_free`. It stays valid after the `Slop *`
+ *   it came from is changed or freed.
+ * - The API never takes ownership of its arguments: keys, values, paths and
+ *   buffers are copied, and can be freed as soon as the call returns.
+ * - The `message` of a `SlopCError` is owned by the struct, and must be freed
+ *   with `slop_error_free`. A `SlopCError` must be zero-initialized before its
+ *   first use, and can be reused: storing a new error frees the old message.
+ *
+ * Conventions:
+ *
+ * - Strings are NUL-terminated UTF-8. A key that isn't valid UTF-8 is treated
+ *   as missing.
+ * - Functions that can fail return `NULL` or `false`, and store the error in
+ *   their last argument, unless it is `NULL`.
+ * - Pointers must be valid and not `NULL`, unless stated otherwise.
+ * - A `Slop *` may not be used from more than one thread at a time.
+ */
+
+#ifndef SLOP_H
+#define SLOP_H
+
+/* Generated by cbindgen from src/capi.rs. Do not edit. */
+
+#include <stdbool.h>
+#include <stddef.h>
+
+/* A parsed SLOP object. Free it with `slop_free`. */
+typedef struct Slop Slop;
+
+/**
+ * An error returned by the C API.
+ */
+typedef struct SlopCError {
+  /**
+   * A description of the error, or `NULL` if no error was stored. Owned by
+   * the struct; free it with `slop_error_free`.
+   */
+  char *message;
+  /**
+   * The 1-based number of the line the error is in, or `0` if the error
+   * isn't about a line.
+   */
+  size_t line;
+} SlopCError;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns a new, empty `Slop *`.
+ */
+Slop *slop_new(void);
+
+/**
+ * Frees a `Slop *`. Does nothing if `slop` is `NULL`.
+ *
+ * ## Safety
+ *
+ * `slop` must have been returned by this API, and not freed yet.
+ */
+void slop_free(Slop *slop);
+
+/**
+ * Opens a SLOP file, resolving its includes. (See [Slop::open])
+ *
+ * Returns `NULL` if the file can't be read or parsed.
+ *
+ * ## Safety
+ *
+ * See the [conventions](self#conventions).
+ */
+Slop *slop_open(const char *path, struct SlopCError *error);
+
+/**
+ * Parses the `len` bytes at `buf` as a SLOP string. The buffer doesn't have
+ * to be NUL-terminated, and may be UTF-8 or UTF-16. (See [Slop::from_bytes])
+ *
+ * Returns `NULL` if the buffer is not valid in its encoding or can't be
+ * parsed.
+ *
+ * ## Safety
+ *
+ * `buf` must point to `len` readable bytes. It may be `NULL` if `len` is `0`.
+ */
+Slop *slop_parse(const char *buf, size_t len, struct SlopCError *error);
+
+/**
+ * Returns a copy of the value of the string KV `key`, or `NULL` if there is
+ * no such KV, or if the value contains a NUL character.
+ *
+ * ## Safety
+ *
+ * See the [conventions](self#conventions).
+ */
+char *slop_get_string(const Slop *slop, const char *key);
+
+/**
+ * Stores the number of items of the list KV `key` in `len`.
+ *
+ * Returns `false`, leaving `len` untouched, if there is no such KV.
+ *
+ * ## Safety
+ *
+ * See the [conventions](self#conventions).
+ */
+bool slop_list_len(const Slop *slop, const char *key, size_t *len);
+
+/**
+ * Returns a copy of the item at `index` of the list KV `key`, or `NULL` if
+ * there is no such KV or item, or if the item contains a NUL character.
+ *
+ * ## Safety
+ *
+ * See the [conventions](self#conventions).
+ */
+char *slop_list_item(const Slop *slop, const char *key, size_t index);
+
+/**
+ * Inserts a string KV, replacing any KV with the same key.
+ * (See [Slop::insert])
+ *
+ * Returns `false` if the key or the value is not valid UTF-8, or if the KV
+ * wouldn't be read back unchanged from a saved file. (See [Slop::validate])
+ *
+ * ## Safety
+ *
+ * See the [conventions](self#conventions).
+ */
+bool slop_insert_string(Slop *slop, const char *key, const char *value, struct SlopCError *error);
+
+/**
+ * Inserts a list KV with the `len` strings at `items`, replacing any KV with
+ * the same key. (See [Slop::insert])
+ *
+ * Returns `false` if the key or an item is not valid UTF-8, or if the KV
+ * wouldn't be read back unchanged from a saved file. (See [Slop::validate])
+ *
+ * ## Safety
+ *
+ * `items` must point to `len` strings. It may be `NULL` if `len` is `0`. See
+ * also the [conventions](self#conventions).
+ */
+bool slop_insert_list(Slop *slop,
+                      const char *key,
+                      const char *const *items,
+                      size_t len,
+                      struct SlopCError *error);
+
+/**
+ * Removes the KV `key`.
+ *
+ * Returns `false` if there is no such KV.
+ *
+ * ## Safety
+ *
+ * See the [conventions](self#conventions).
+ */
+bool slop_remove(Slop *slop, const char *key);
+
+/**
+ * Writes the `Slop *` to the file at `path`. If `pretty` is `true`, the
+ * values of lists are indented. (See [Slop::save] and [Slop::save_pretty])
+ *
+ * Returns `false` if the file can't be written.
+ *
+ * ## Safety
+ *
+ * See the [conventions](self#conventions).
+ */
+bool slop_save(const Slop *slop, const char *path, bool pretty, struct SlopCError *error);
+
+/**
+ * Frees a string returned by the API. Does nothing if `string` is `NULL`.
+ *
+ * ## Safety
+ *
+ * `string` must have been returned by this API, and not freed yet.
+ */
+void slop_string_free(char *string);
+
+/**
+ * Frees the message of the error, and resets it to `NULL` and line `0`.
+ * Does nothing if `error` is `NULL`.
+ *
+ * ## Safety
+ *
+ * `error` must be zero-initialized or filled in by this API.
+ */
+void slop_error_free(struct SlopCError *error);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* SLOP_H */
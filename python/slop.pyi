@@ -0,0 +1,30 @@
+import os
+from typing import Iterator, Mapping, Optional, Union
+
+Value = Union[str, list[str]]
+
+class SlopError(ValueError):
+    kind: str
+    help: str
+    line: Optional[int]
+    column: Optional[int]
+    path: Optional[str]
+
+class Slop:
+    def __init__(self, mapping: Optional[Mapping[str, Value]] = None) -> None: ...
+    def __getitem__(self, key: str) -> Value: ...
+    def __setitem__(self, key: str, value: Value) -> None: ...
+    def __delitem__(self, key: str) -> None: ...
+    def __contains__(self, key: object) -> bool: ...
+    def __len__(self) -> int: ...
+    def __iter__(self) -> Iterator[str]: ...
+    def get(self, key: str, default: Optional[Value] = None) -> Optional[Value]: ...
+    def keys(self) -> list[str]: ...
+    def values(self) -> list[Value]: ...
+    def items(self) -> list[tuple[str, Value]]: ...
+    def to_dict(self) -> dict[str, Value]: ...
+    def save(self, path: Union[str, os.PathLike[str]], *, pretty: bool = False) -> None: ...
+
+def loads(s: str) -> Slop: ...
+def load(path: Union[str, os.PathLike[str]], *, includes: bool = False) -> Slop: ...
+def dumps(obj: Mapping[str, Value], *, pretty: bool = False) -> str: ...
@@ -0,0 +1,263 @@
+//! Python bindings for [slop_rs], built with PyO3 and maturin.
+//!
+//! ```python
+//! import slop
+//!
+//! config = slop.loads("name=example\ntags{\n    a\n    b\n}")
+//! config["name"]  # "example"
+//! config["tags"]  # ["a", "b"]
+//!
+//! config["version"] = "2"
+//! slop.dumps(config, pretty=True)
+//! ```
+
+use std::{io, path::PathBuf};
+
+use pyo3::{
+    create_exception,
+    exceptions::{PyKeyError, PyOSError, PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyDict, PyList, PyString},
+};
+use slop_rs::{FsResolver, IncludeOptions, Slop, SlopValue, error};
+
+create_exception!(
+    slop,
+    SlopError,
+    PyValueError,
+    "Raised when a SLOP string can't be parsed, or a key or value can't be written to one.\n\n\
+    `line` and `column` are the 1-based position of the error, or `None`, `path` is the file \
+    it is in, or `None`, `kind` names the problem, such as `invalid-line`, and `help` is a hint \
+    about how to fix it."
+);
+
+/// Parses a SLOP string.
+#[pyfunction]
+fn loads(s: &str) -> PyResult<PySlop> {
+    Ok(PySlop { inner: s.parse().map_err(to_py)? })
+}
+
+/// Opens a SLOP file. If `includes` is true, its `#!include` directives are
+/// resolved; only use it on files you trust, since they can include any file.
+#[pyfunction]
+#[pyo3(signature = (path, *, includes = false))]
+fn load(path: PathBuf, includes: bool) -> PyResult<PySlop> {
+    let slop = if includes {
+        Slop::open_with(path, &FsResolver, &IncludeOptions::default())
+    } else {
+        Slop::open(path)
+    };
+
+    Ok(PySlop { inner: slop.map_err(to_py)? })
+}
+
+/// Converts a `Slop`, or any mapping whose values are `str` or `list[str]`,
+/// into a SLOP string. If `pretty` is true, the values of lists are indented.
+#[pyfunction]
+#[pyo3(signature = (obj, *, pretty = false))]
+fn dumps(obj: &Bound<'_, PyAny>, pretty: bool) -> PyResult<String> {
+    let slop = match obj.extract::<PyRef<PySlop>>() {
+        Ok(slop) => slop.inner.clone(),
+        Err(_) => from_mapping(obj)?,
+    };
+
+    Ok(if pretty { slop.to_string_pretty() } else { slop.to_string() })
+}
+
+/// A parsed SLOP object. Behaves like a `dict` whose values are `str` (for
+/// string KVs) or `list[str]` (for list KVs). Lists are returned as copies.
+///
+/// Iterates over the keys in sorted order.
+#[pyclass(name = "Slop", module = "slop", eq)]
+#[derive(PartialEq)]
+struct PySlop {
+    inner: Slop,
+}
+
+#[pymethods]
+impl PySlop {
+    #[new]
+    #[pyo3(signature = (mapping = None))]
+    fn new(mapping: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        Ok(Self { inner: mapping.map(from_mapping).transpose()?.unwrap_or_default() })
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        match self.inner.get(key) {
+            Some(value) => to_object(py, value),
+            None => Err(PyKeyError::new_err(key.to_string())),
+        }
+    }
+
+    fn __setitem__(&mut self, key: String, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        insert(&mut self.inner, key, value)
+    }
+
+    fn __delitem__(&mut self, key: &str) -> PyResult<()> {
+        match self.inner.remove(key) {
+            Some(_) => Ok(()),
+            None => Err(PyKeyError::new_err(key.to_string())),
+        }
+    }
+
+    // Like a `dict`, a key of any other type is just missing.
+    fn __contains__(&self, key: &Bound<'_, PyAny>) -> PyResult<bool> {
+        match key.cast::<PyString>() {
+            Ok(key) => Ok(self.inner.contains_key(key.to_str()?)),
+            Err(_) => Ok(false),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.iter().len()
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        PyList::new(py, self.keys())?.try_iter().map(Bound::into_any)
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!("Slop({})", self.to_dict(py)?.repr()?))
+    }
+
+    /// Returns the value of `key`, or `default` if there is no such KV.
+    #[pyo3(signature = (key, default = None))]
+    fn get<'py>(&self, py: Python<'py>, key: &str, default: Option<Bound<'py, PyAny>>)
+        -> PyResult<Option<Bound<'py, PyAny>>>
+    {
+        match self.inner.get(key) {
+            Some(value) => to_object(py, value).map(Some),
+            None => Ok(default),
+        }
+    }
+
+    /// Returns the sorted keys.
+    fn keys(&self) -> Vec<&str> {
+        self.sorted().into_iter().map(|(k, _)| k).collect()
+    }
+
+    /// Returns the values, sorted by their keys.
+    fn values<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyAny>>> {
+        self.sorted().into_iter().map(|(_, v)| to_object(py, v)).collect()
+    }
+
+    /// Returns the `(key, value)` pairs, sorted by their keys.
+    fn items<'py>(&self, py: Python<'py>) -> PyResult<Vec<(&str, Bound<'py, PyAny>)>> {
+        self.sorted().into_iter().map(|(k, v)| Ok((k, to_object(py, v)?))).collect()
+    }
+
+    /// Returns the KVs as a new `dict`.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+
+        for (key, value) in self.items(py)? {
+            dict.set_item(key, value)?;
+        }
+
+        Ok(dict)
+    }
+
+    /// Writes the KVs to the file at `path`. If `pretty` is true, the values
+    /// of lists are indented.
+    #[pyo3(signature = (path, *, pretty = false))]
+    fn save(&self, path: PathBuf, pretty: bool) -> PyResult<()> {
+        let result = if pretty { self.inner.save_pretty(path) } else { self.inner.save(path) };
+        result.map_err(to_py)
+    }
+}
+
+impl PySlop {
+    // Returns the KVs, sorted by their keys.
+    fn sorted(&self) -> Vec<(&str, &SlopValue)> {
+        let mut kvs: Vec<_> = self.inner.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        kvs.sort_unstable_by_key(|(k, _)| *k);
+        kvs
+    }
+}
+
+// Builds a `Slop` from any mapping with `str` keys.
+fn from_mapping(mapping: &Bound<'_, PyAny>) -> PyResult<Slop> {
+    let mut slop = Slop::new();
+
+    for key in mapping.try_iter()? {
+        let key = key?;
+        let value = mapping.get_item(&key)?;
+        insert(&mut slop, key.extract()?, &value)?;
+    }
+
+    Ok(slop)
+}
+
+fn insert(slop: &mut Slop, key: String, value: &Bound<'_, PyAny>) -> PyResult<()> {
+    let value = if let Ok(s) = value.cast::<PyString>() {
+        SlopValue::String(s.to_str()?.to_string())
+    } else if let Ok(items) = value.extract::<Vec<String>>() {
+        SlopValue::List(items)
+    } else {
+        return Err(PyTypeError::new_err(format!(
+            "the value of `{key}` must be a str or a list of str, not {}",
+            value.get_type().name()?,
+        )));
+    };
+
+    // Checks the key and the value the way a saved file would read them back.
+    slop.insert(key, value).map_err(to_py)?;
+    Ok(())
+}
+
+fn to_object<'py>(py: Python<'py>, value: &SlopValue) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        SlopValue::String(s) => Ok(PyString::new(py, s).into_any()),
+        SlopValue::List(l) => Ok(PyList::new(py, l)?.into_any()),
+    }
+}
+
+// Converts the error into a `SlopError`, or an `OSError` with the `filename` if
+// a file couldn't be read or written.
+fn to_py(e: error::SlopError) -> PyErr {
+    let path = e.path().map(|p| p.display().to_string());
+
+    if let Some(io) = e.io_error() {
+        return match (io.raw_os_error(), path) {
+            // `OSError(errno, strerror, filename)` picks the subclass, such as
+            // `FileNotFoundError`, from the errno.
+            (Some(errno), Some(path)) => {
+                let message = io.to_string();
+                let strerror = message.split(" (os error").next().unwrap_or(&message);
+                PyOSError::new_err((errno, strerror.to_string(), path))
+            },
+            _ => PyErr::from(io::Error::new(io.kind(), io.to_string())),
+        };
+    }
+
+    Python::attach(|py| {
+        let err = SlopError::new_err(e.to_string());
+        let value = err.value(py);
+
+        // Setting the attributes of a new exception can't fail.
+        value.setattr("kind", e.kind().as_str()).unwrap();
+        value.setattr("help", e.help()).unwrap();
+        value.setattr("line", e.line().map(|l| l + 1)).unwrap();
+        value.setattr("column", e.column().map(|c| c + 1)).unwrap();
+        value.setattr("path", path).unwrap();
+        err
+    })
+}
+
+/// Reads and writes SLOP, the data storage language.
+#[pymodule]
+fn slop(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(loads, m)?)?;
+    m.add_function(wrap_pyfunction!(load, m)?)?;
+    m.add_function(wrap_pyfunction!(dumps, m)?)?;
+    m.add_class::<PySlop>()?;
+    m.add("SlopError", m.py().get_type::<SlopError>())?;
+
+    // Makes `isinstance(x, collections.abc.MutableMapping)` true.
+    m.py()
+        .import("collections.abc")?
+        .getattr("MutableMapping")?
+        .call_method1("register", (m.py().get_type::<PySlop>(),))?;
+
+    Ok(())
+}
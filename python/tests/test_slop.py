@@ -0,0 +1,137 @@
+"""Tests the bindings. Run them with `pytest` after `maturin develop`."""
+
+import collections.abc
+
+import pytest
+
+import slop
+
+
+def test_loads_and_dumps():
+    config = slop.loads("name=example\ntags{\n    a\n    b\n}")
+
+    assert config["name"] == "example"
+    assert config["tags"] == ["a", "b"]
+    assert list(config) == ["name", "tags"]
+    assert len(config) == 2
+    assert isinstance(config, collections.abc.MutableMapping)
+
+    assert slop.loads(slop.dumps(config)) == config
+    assert slop.dumps(config, pretty=True) in (
+        "name=example\ntags{\n    a\n    b\n}\n",
+        "tags{\n    a\n    b\n}\nname=example\n",
+    )
+    assert slop.loads(slop.dumps({"a": "1", "b": ["x"]})) == slop.Slop({"a": "1", "b": ["x"]})
+
+
+def test_lists_are_copies():
+    config = slop.loads("tags{\n    a\n}")
+    config["tags"].append("b")
+
+    assert config["tags"] == ["a"]
+
+
+def test_missing_keys():
+    config = slop.loads("a=1")
+
+    with pytest.raises(KeyError):
+        config["b"]
+
+    with pytest.raises(KeyError):
+        del config["b"]
+
+    assert config.get("b") is None
+    assert config.get("b", "2") == "2"
+
+
+def test_contains():
+    config = slop.loads("a=1")
+
+    assert "a" in config
+    assert "b" not in config
+    assert 1 not in config
+    assert None not in config
+
+
+def test_invalid_values():
+    config = slop.Slop()
+
+    with pytest.raises(TypeError):
+        config["a"] = 1
+
+    with pytest.raises(slop.SlopError) as e:
+        config["a="] = "1"
+
+    assert e.value.kind == "invalid-key"
+
+    with pytest.raises(slop.SlopError) as e:
+        config["tags"] = ["  indented"]
+
+    assert e.value.kind == "invalid-value"
+
+
+@pytest.mark.parametrize("key", ["#a", " a", "\ta"])
+def test_keys_that_would_not_read_back(key):
+    with pytest.raises(slop.SlopError) as e:
+        slop.Slop({key: "1"})
+
+    assert e.value.kind == "invalid-key"
+
+    with pytest.raises(slop.SlopError):
+        slop.dumps({key: "1"})
+
+    with pytest.raises(slop.SlopError):
+        slop.Slop()[key] = "1"
+
+
+def test_error_attributes():
+    with pytest.raises(slop.SlopError) as e:
+        slop.loads("a=1\n    not a kv")
+
+    assert isinstance(e.value, ValueError)
+    assert e.value.kind == "invalid-line"
+    assert e.value.line == 2
+    assert e.value.column == 5
+    assert e.value.path is None
+    assert e.value.help
+
+
+def test_error_path(tmp_path):
+    path = tmp_path / "bad.slop"
+    path.write_text("a=1\nlist{\nb")
+
+    with pytest.raises(slop.SlopError) as e:
+        slop.load(path)
+
+    assert e.value.kind == "unclosed-list"
+    assert e.value.line == 2
+    assert e.value.path == str(path)
+
+
+def test_os_errors(tmp_path):
+    path = tmp_path / "missing.slop"
+
+    with pytest.raises(FileNotFoundError) as e:
+        slop.load(path)
+
+    assert e.value.filename == str(path)
+
+    with pytest.raises(OSError):
+        slop.Slop().save(tmp_path / "missing" / "dir.slop")
+
+
+def test_save_and_load(tmp_path):
+    path = tmp_path / "config.slop"
+    config = slop.Slop({"a": "1", "b": ["x", "y"]})
+    config.save(path, pretty=True)
+
+    assert slop.load(path) == config
+
+
+def test_includes(tmp_path):
+    (tmp_path / "base.slop").write_text("a=1")
+    path = tmp_path / "main.slop"
+    path.write_text("#!include base.slop\nb=2")
+
+    assert "a" not in slop.load(path)
+    assert slop.load(path, includes=True) == slop.Slop({"a": "1", "b": "2"})
@@ -0,0 +1,213 @@
+//! Asynchronous versions of the I/O functions, built on [tokio]. Requires the
+//! `async` feature.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! These functions must be called from within a tokio runtime.
+
+use std::{io, path::Path, vec};
+
+use tokio::{
+    fs,
+    io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt},
+    task,
+};
+
+use crate::{
+    ParseOptions,
+    Slop,
+    SlopValue,
+    encoding::{Encoding, decode, decode_line},
+    error::{SlopError, SlopResult},
+    parse::Parser,
+};
+
+impl Slop {
+    /// Same as [Slop::open], but doesn't block the current thread.
+    ///
+    /// The file is read on tokio's blocking thread pool, with the same rules
+    /// as [Slop::open].
+    ///
+    /// If the runtime shuts down before the file is read, returns a
+    /// [SlopError::Io] instead. A panic while reading is resumed in the
+    /// calling task.
+    pub async fn open_async<P: AsRef<Path>>(path: P) -> SlopResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let error_path = path.clone();
+
+        match task::spawn_blocking(move || Slop::open(path)).await {
+            Ok(result) => result,
+            Err(e) => match e.try_into_panic() {
+                Ok(panic) => std::panic::resume_unwind(panic),
+                Err(e) => Err(SlopError::from(io::Error::other(e)).with_path(error_path)),
+            },
+        }
+    }
+
+    /// Same as [Slop::save], but doesn't block the current thread.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// let path = std::env::temp_dir().join(format!("slop-async-{}.slop", std::process::id()));
+    /// let slop: Slop = "a=1\nb{\nx\n}".parse().unwrap();
+    ///
+    /// runtime.block_on(async {
+    ///     slop.save_async(&path).await.unwrap();
+    ///     assert_eq!(Slop::open_async(&path).await.unwrap(), slop);
+    ///     tokio::fs::remove_file(&path).await.unwrap();
+    /// });
+    /// ```
+    #[inline]
+    pub async fn save_async<P: AsRef<Path>>(&self, path: P) -> SlopResult<()> {
+        fs::write(&path, self.to_string()).await.map_err(|e| SlopError::from(e).with_path(path))
+    }
+
+    /// Same as [Slop::save_pretty], but doesn't block the current thread.
+    #[inline]
+    pub async fn save_pretty_async<P: AsRef<Path>>(&self, path: P) -> SlopResult<()> {
+        let contents = self.to_string_pretty();
+        fs::write(&path, contents).await.map_err(|e| SlopError::from(e).with_path(path))
+    }
+
+    /// Reads a SLOP string from the reader and parses it into a new [Slop].
+    ///
+    /// See [SlopReader] to read the KVs one at a time instead.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    /// let slop_str = "a=1\nb{\nx\ny\n}";
+    ///
+    /// let slop = runtime.block_on(Slop::read_async(slop_str.as_bytes())).unwrap();
+    ///
+    /// assert_eq!(slop, slop_str.parse().unwrap());
+    /// ```
+    pub async fn read_async<R: AsyncBufRead + Unpin>(reader: R) -> SlopResult<Self> {
+        let mut reader = SlopReader::new(reader);
+        let mut slop = Slop::new();
+
+        while let Some((key, value)) = reader.next_kv().await? {
+            slop.insert_unchecked(key, value);
+        }
+
+        Ok(slop)
+    }
+}
+
+/// Parses SLOP from an [AsyncBufRead] one KV at a time, without reading the
+/// whole input first.
+///
+/// Follows the same rules as [Slop::append_slop_string], and returns the same
+/// errors. The input is decoded like [decode](crate::decode), so invalid UTF-8
+/// returns [SlopError::InvalidEncoding]. UTF-8 is decoded one line at a time,
+/// but UTF-16 is read whole before it's parsed.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{SlopReader, SlopValue};
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+///
+/// runtime.block_on(async {
+///     let mut reader = SlopReader::new("# comment\na=1\nb{\nx\n}".as_bytes());
+///
+///     assert_eq!(reader.next_kv().await.unwrap(), Some(("a".to_string(), "1".into())));
+///     assert_eq!(reader.next_kv().await.unwrap(), Some(("b".to_string(), vec!["x"].into())));
+///     assert_eq!(reader.next_kv().await.unwrap(), None);
+/// });
+/// ```
+#[derive(Debug)]
+pub struct SlopReader<R> {
+    reader: R,
+    parser: Parser,
+    // The index of the next line.
+    line_index: usize,
+    // The position of the next line in the input.
+    offset: usize,
+    // `true` once the last line was read.
+    done: bool,
+    buf: Vec<u8>,
+    line: String,
+    // The lines left of a UTF-16 input, which is decoded whole.
+    utf16_lines: Option<vec::IntoIter<String>>,
+}
+
+impl<R: AsyncBufRead + Unpin> SlopReader<R> {
+    /// Constructs a [SlopReader] that reads from the start of `reader`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: Parser::new(ParseOptions::default()),
+            line_index: 0,
+            offset: 0,
+            done: false,
+            buf: Vec::new(),
+            line: String::new(),
+            utf16_lines: None,
+        }
+    }
+
+    /// Returns the reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads the next KV, or returns [None] at the end of the input.
+    pub async fn next_kv(&mut self) -> SlopResult<Option<(String, SlopValue)>> {
+        while let Some(index) = self.read_line().await? {
+            if let Some(kv) = self.parser.kv(index, &self.line)? {
+                return Ok(Some(kv));
+            }
+        }
+
+        self.parser.end()?;
+        Ok(None)
+    }
+
+    // Reads and decodes the next line into `self.line`, without its `\n`, and
+    // returns its index, or [None] at the end of the input.
+    async fn read_line(&mut self) -> SlopResult<Option<usize>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let index = self.line_index;
+        self.line_index += 1;
+
+        if index == 0 && Encoding::detect(self.reader.fill_buf().await?) != Encoding::Utf8 {
+            let mut bytes = Vec::new();
+            self.reader.read_to_end(&mut bytes).await?;
+            let lines: Vec<_> = decode(&bytes, false)?.split('\n').map(String::from).collect();
+            self.utf16_lines = Some(lines.into_iter());
+        }
+
+        if let Some(lines) = &mut self.utf16_lines {
+            // Like `str::split`, there is always at least one line.
+            self.line = lines.next().unwrap_or_default();
+            self.done = lines.len() == 0;
+            return Ok(Some(index));
+        }
+
+        // Like `str::split`, an input that ends in a line break has an empty
+        // last line.
+        self.buf.clear();
+        let bytes = self.reader.read_until(b'\n', &mut self.buf).await?;
+        self.done = self.buf.last() != Some(&b'\n');
+
+        let line = self.buf.strip_suffix(b"\n").unwrap_or(&self.buf);
+        self.line.clear();
+        self.line.push_str(&decode_line(line, index, self.offset, false)?);
+        self.offset += bytes;
+
+        Ok(Some(index))
+    }
+}
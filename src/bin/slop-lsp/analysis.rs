@@ -0,0 +1,454 @@
+//! Turns SLOP strings into the results of language server requests.
+//!
+//! Every function here is independent from the protocol's transport, and
+//! works on the full text of a document.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use lsp_types::{
+    CompletionItem,
+    CompletionItemKind,
+    Diagnostic,
+    DiagnosticSeverity,
+    DocumentSymbol,
+    FoldingRange,
+    FoldingRangeKind,
+    Hover,
+    HoverContents,
+    MarkupContent,
+    MarkupKind,
+    NumberOrString,
+    Position,
+    Range,
+    SymbolKind,
+    TextEdit,
+};
+use slop_rs::{
+    DocEntry,
+    FormatOptions,
+    IncludeOptions,
+    KvEntry,
+    Severity,
+    SlopDocument,
+    SlopValue,
+    include,
+    schema::{KeySchema, SlopSchema, ValueKind},
+};
+
+/// Returns the lints of the text, the error of the parser if no lint is on its
+/// line, and the schema violations if the text can be parsed.
+///
+/// The text is parsed the way [Slop::open](slop_rs::Slop::open) would read it
+/// once saved, so its encoding is checked too.
+pub fn diagnostics(text: &str, schema: Option<&SlopSchema>) -> Vec<Diagnostic> {
+    let lines = lines(text);
+    let mut diagnostics: Vec<Diagnostic> = slop_rs::lint(text)
+        .into_iter()
+        .map(|lint| Diagnostic {
+            range: line_range(&lines, lint.line, lint.column),
+            severity: Some(match lint.severity() {
+                Severity::Warning => DiagnosticSeverity::WARNING,
+                Severity::Error => DiagnosticSeverity::ERROR,
+            }),
+            code: Some(NumberOrString::String(lint.id.to_string())),
+            source: Some("slop".to_string()),
+            message: lint.message,
+            ..Default::default()
+        })
+        .collect();
+
+    let doc = match slop_rs::decode(text.as_bytes(), false)
+        .and_then(|decoded| decoded.parse::<SlopDocument>())
+    {
+        Ok(doc) => doc,
+        Err(e) => {
+            let line = e.line().unwrap_or(0);
+
+            if !diagnostics.iter().any(|d| d.range.start.line as usize == line) {
+                diagnostics.push(Diagnostic {
+                    range: line_range(&lines, line, e.column().unwrap_or(0)),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    code: Some(NumberOrString::String(e.kind().to_string())),
+                    source: Some("slop".to_string()),
+                    message: e.root().to_string(),
+                    ..Default::default()
+                });
+            }
+
+            return diagnostics;
+        },
+    };
+    let Some(schema) = schema else { return diagnostics };
+
+    if let Err(violations) = schema.validate_document(&doc) {
+        diagnostics.extend(violations.into_iter().map(|v| Diagnostic {
+            range: line_range(&lines, v.line.unwrap_or(0), 0),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String("schema".to_string())),
+            source: Some("slop".to_string()),
+            message: format!("`{}`: {}", v.key, v.kind),
+            ..Default::default()
+        }));
+    }
+
+    diagnostics
+}
+
+/// Returns an outline with one symbol per KV.
+pub fn symbols(text: &str) -> Vec<DocumentSymbol> {
+    let Ok(doc) = text.parse::<SlopDocument>() else { return vec![] };
+    let lines = lines(text);
+
+    doc.kvs()
+        .filter_map(|kv| {
+            let start = kv.line()?;
+            let end = start + kv.source().len() - 1;
+
+            #[allow(deprecated)]
+            Some(DocumentSymbol {
+                name: kv.key().to_string(),
+                detail: Some(match kv.value() {
+                    SlopValue::String(s) => s.clone(),
+                    SlopValue::List(l) => format!("{} items", l.len()),
+                }),
+                kind: match kv.value() {
+                    SlopValue::String(_) => SymbolKind::STRING,
+                    SlopValue::List(_) => SymbolKind::ARRAY,
+                },
+                tags: None,
+                deprecated: None,
+                range: Range::new(
+                    Position::new(start as u32, 0),
+                    Position::new(end as u32, utf16_len(lines.get(end).unwrap_or(&""))),
+                ),
+                selection_range: key_range(&lines, kv),
+                children: None,
+            })
+        })
+        .collect()
+}
+
+/// Returns a folding range for every list KV.
+pub fn folding_ranges(text: &str) -> Vec<FoldingRange> {
+    let Ok(doc) = text.parse::<SlopDocument>() else { return vec![] };
+
+    doc.kvs()
+        .filter(|kv| kv.value().is_list())
+        .filter_map(|kv| {
+            let start = kv.line()? as u32;
+            Some(FoldingRange {
+                start_line: start,
+                end_line: start + kv.source().len() as u32 - 1,
+                kind: Some(FoldingRangeKind::Region),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Returns an edit that replaces the whole text with its formatted version,
+/// or [None] if the text can't be parsed.
+pub fn formatting(text: &str) -> Option<Vec<TextEdit>> {
+    let formatted = slop_rs::format(text, &FormatOptions::default()).ok()?;
+
+    if formatted == text {
+        return Some(vec![]);
+    }
+
+    let lines = lines(text);
+    let last = lines.len() - 1;
+
+    Some(vec![TextEdit {
+        range: Range::new(
+            Position::new(0, 0),
+            Position::new(last as u32, utf16_len(lines[last])),
+        ),
+        new_text: formatted,
+    }])
+}
+
+/// Returns a description of the key under the cursor.
+pub fn hover(text: &str, position: Position, schema: Option<&SlopSchema>) -> Option<Hover> {
+    let doc = text.parse::<SlopDocument>().ok()?;
+    let lines = lines(text);
+    let kv = key_at(&doc, &lines, position)?;
+
+    let kind = match kv.value() {
+        SlopValue::String(_) => "string kv",
+        SlopValue::List(_) => "list kv",
+    };
+    let mut value = format!("`{}` ({kind})", kv.key());
+
+    if let Some(key_schema) = schema.and_then(|s| s.get(kv.key())) {
+        value.push_str(&describe(key_schema));
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value }),
+        range: Some(key_range(&lines, kv)),
+    })
+}
+
+/// Returns the schema's keys that aren't in the text yet, or the allowed
+/// values of the KV the cursor is in.
+pub fn completion(text: &str, position: Position, schema: Option<&SlopSchema>)
+    -> Vec<CompletionItem>
+{
+    let Some(schema) = schema else { return vec![] };
+    let lines = lines(text);
+    let line = lines.get(position.line as usize).copied().unwrap_or("");
+    let before_cursor = &line[..char_to_byte(line, utf16_to_char(line, position.character))];
+
+    let doc = text.parse::<SlopDocument>().ok();
+    let containing_list = doc.as_ref().and_then(|doc| doc.kvs().find(|kv| {
+        kv.value().is_list() && kv.line().is_some_and(|start| {
+            let line = position.line as usize;
+            line > start && line < start + kv.source().len() - 1
+        })
+    }));
+
+    let value_key = match (before_cursor.split_once('='), containing_list) {
+        (_, Some(kv)) => Some(kv.key()),
+        (Some((key, _)), None) => Some(key.trim_start()),
+        (None, None) => None,
+    };
+
+    if let Some(key) = value_key {
+        return schema
+            .get(key)
+            .and_then(|s| s.allowed.as_ref())
+            .into_iter()
+            .flatten()
+            .map(|v| CompletionItem {
+                label: v.clone(),
+                kind: Some(CompletionItemKind::ENUM_MEMBER),
+                ..Default::default()
+            })
+            .collect();
+    }
+
+    schema
+        .iter()
+        .filter(|(key, _)| !doc.as_ref().is_some_and(|d| d.contains_key(key)))
+        .map(|(key, key_schema)| CompletionItem {
+            label: key.clone(),
+            kind: Some(CompletionItemKind::PROPERTY),
+            detail: key_schema.description.clone(),
+            insert_text: Some(match key_schema.kind {
+                Some(ValueKind::List) => format!("{key}{{"),
+                _ => format!("{key}="),
+            }),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Returns the file and range that the cursor points to: the included file if
+/// the cursor is on an `#!include` directive, or else the KV that is in
+/// effect for the key under the cursor, which is the last KV with that key,
+/// taking included files into account.
+///
+/// The returned path is [None] if the target is in the text itself. Includes
+/// are only followed if the path of the text is known.
+pub fn definition(text: &str, path: Option<&Path>, position: Position)
+    -> Option<(Option<PathBuf>, Range)>
+{
+    let doc = text.parse::<SlopDocument>().ok()?;
+    let lines = lines(text);
+    let dir = path.and_then(Path::parent);
+
+    if let Some((_, include)) = includes(&doc).find(|(l, _)| *l == position.line as usize) {
+        let target = dir?.join(include).canonicalize().ok()?;
+        return Some((Some(target), Range::default()));
+    }
+
+    let key = key_at(&doc, &lines, position)?.key();
+    let path = path.map(|p| p.canonicalize().unwrap_or_else(|_| p.to_path_buf()));
+    let mut chain: Vec<_> = path.iter().cloned().collect();
+
+    last_definition(&doc, &lines, dir, key, &mut chain)
+        .map(|(target, range)| (target.filter(|t| Some(t) != path.as_ref()), range))
+}
+
+// Returns the last KV with the key in the document or the files it includes.
+// The chain holds the files being searched, to avoid cycles.
+fn last_definition(
+    doc: &SlopDocument,
+    lines: &[&str],
+    dir: Option<&Path>,
+    key: &str,
+    chain: &mut Vec<PathBuf>,
+) -> Option<(Option<PathBuf>, Range)> {
+    let mut definition = None;
+
+    for entry in doc.entries() {
+        match entry {
+            DocEntry::Trivia(trivia) => {
+                let Some(include) = include::directive(trivia) else { continue };
+                let found = dir.and_then(|dir| included_definition(dir, include, key, chain));
+
+                if found.is_some() {
+                    definition = found;
+                }
+            },
+            DocEntry::Kv(kv) if kv.key() == key => {
+                definition = Some((chain.last().cloned(), key_range(lines, kv)));
+            },
+            DocEntry::Kv(_) => {},
+        }
+    }
+
+    definition
+}
+
+// Searches the included file for the last KV with the key.
+fn included_definition(dir: &Path, include: &str, key: &str, chain: &mut Vec<PathBuf>)
+    -> Option<(Option<PathBuf>, Range)>
+{
+    let path = dir.join(include).canonicalize().ok()?;
+
+    if chain.contains(&path) || chain.len() > IncludeOptions::default().max_depth {
+        return None;
+    }
+
+    let text = fs::read_to_string(&path).ok()?;
+    let doc = text.parse::<SlopDocument>().ok()?;
+    let dir = path.parent().map(Path::to_path_buf);
+
+    chain.push(path);
+    let definition = last_definition(&doc, &lines(&text), dir.as_deref(), key, chain);
+    chain.pop();
+
+    definition
+}
+
+// Iterates over the `#!include` directives of the document, along with the
+// index of their lines.
+fn includes(doc: &SlopDocument) -> impl Iterator<Item = (usize, &str)> {
+    let mut line = 0;
+
+    doc.entries().filter_map(move |entry| {
+        let index = line;
+
+        match entry {
+            DocEntry::Trivia(trivia) => {
+                line += 1;
+                include::directive(trivia).map(|path| (index, path))
+            },
+            DocEntry::Kv(kv) => {
+                line += kv.source().len();
+                None
+            },
+        }
+    })
+}
+
+// Splits the text into lines, without their terminators.
+fn lines(text: &str) -> Vec<&str> {
+    text.split('\n').map(|l| l.strip_suffix('\r').unwrap_or(l)).collect()
+}
+
+// Returns the KV whose key is under the cursor.
+fn key_at<'a>(doc: &'a SlopDocument, lines: &[&str], position: Position) -> Option<&'a KvEntry> {
+    doc.kvs().find(|kv| {
+        let range = key_range(lines, kv);
+        kv.line() == Some(position.line as usize)
+            && position.character >= range.start.character
+            && position.character <= range.end.character
+    })
+}
+
+// Returns the range of the KV's key.
+fn key_range(lines: &[&str], kv: &KvEntry) -> Range {
+    let line_index = kv.line().unwrap_or(0);
+    let line = lines.get(line_index).copied().unwrap_or("");
+    let indent = utf16_len(&line[..line.len() - line.trim_start().len()]);
+
+    Range::new(
+        Position::new(line_index as u32, indent),
+        Position::new(line_index as u32, indent + utf16_len(kv.key())),
+    )
+}
+
+// Returns the range from a character to the end of its line.
+fn line_range(lines: &[&str], line: usize, column: usize) -> Range {
+    let text = lines.get(line).copied().unwrap_or("");
+    let start = utf16_len(&text[..char_to_byte(text, column)]);
+
+    Range::new(
+        Position::new(line as u32, start),
+        Position::new(line as u32, utf16_len(text).max(start)),
+    )
+}
+
+// Describes the schema of a KV as markdown.
+fn describe(key_schema: &KeySchema) -> String {
+    let mut s = String::new();
+
+    if let Some(description) = &key_schema.description {
+        s.push_str(&format!("\n\n{description}"));
+    }
+
+    let mut rules = vec![];
+
+    if key_schema.required {
+        rules.push("required".to_string());
+    }
+    if let Some(kind) = key_schema.kind {
+        rules.push(format!("{kind} kv"));
+    }
+    if let Some(pattern) = &key_schema.pattern {
+        rules.push(format!("matches `{pattern}`"));
+    }
+    if let Some(min) = key_schema.min {
+        rules.push(format!("at least {min}"));
+    }
+    if let Some(max) = key_schema.max {
+        rules.push(format!("at most {max}"));
+    }
+    if let Some(allowed) = &key_schema.allowed {
+        rules.push(format!("one of `{}`", allowed.join("`, `")));
+    }
+    if let Some(min) = key_schema.min_items {
+        rules.push(format!("at least {min} items"));
+    }
+    if let Some(max) = key_schema.max_items {
+        rules.push(format!("at most {max} items"));
+    }
+
+    if !rules.is_empty() {
+        s.push('\n');
+    }
+
+    for rule in rules {
+        s.push_str(&format!("\n- {rule}"));
+    }
+
+    s
+}
+
+fn utf16_len(s: &str) -> u32 {
+    s.encode_utf16().count() as u32
+}
+
+// Converts a character index into a byte index, clamped to the string's length.
+fn char_to_byte(s: &str, column: usize) -> usize {
+    s.char_indices().nth(column).map_or(s.len(), |(i, _)| i)
+}
+
+// Converts a UTF-16 offset into a character index.
+fn utf16_to_char(s: &str, offset: u32) -> usize {
+    let mut units = 0;
+
+    for (i, c) in s.chars().enumerate() {
+        if units >= offset as usize {
+            return i;
+        }
+        units += c.len_utf16();
+    }
+
+    s.chars().count()
+}
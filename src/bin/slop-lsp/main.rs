@@ -0,0 +1,291 @@
+//! The `slop-lsp` language server. Requires the `lsp` feature.
+//!
+//! The server speaks the Language Server Protocol over the standard input and
+//! output, and supports:
+//!
+//! - Diagnostics for parse errors, lints and schema violations.
+//! - An outline with one symbol per KV.
+//! - Folding of list KVs.
+//! - Formatting in the canonical style. (See `FormatOptions`)
+//! - Hover and completion, based on the schema.
+//! - Go-to-definition, which jumps to the KV that is in effect for a key, or
+//!   to the file named by an `#!include` directive.
+//!
+//! The schema is optional. To use one, pass its path as the `schema` field of
+//! the `initializationOptions`. Relative paths are resolved against the
+//! workspace root.
+
+mod analysis;
+
+use std::{collections::HashMap, error::Error, path::PathBuf};
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    CompletionOptions,
+    GotoDefinitionResponse,
+    InitializeParams,
+    Location,
+    MessageType,
+    OneOf,
+    PublishDiagnosticsParams,
+    ServerCapabilities,
+    ShowMessageParams,
+    TextDocumentSyncCapability,
+    TextDocumentSyncKind,
+    Url,
+    notification::{
+        DidChangeTextDocument,
+        DidCloseTextDocument,
+        DidOpenTextDocument,
+        Notification as _,
+        PublishDiagnostics,
+        ShowMessage,
+    },
+    request::{
+        Completion,
+        DocumentSymbolRequest,
+        FoldingRangeRequest,
+        Formatting,
+        GotoDefinition,
+        HoverRequest,
+        Request as _,
+    },
+};
+use slop_rs::schema::SlopSchema;
+
+type BoxResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+struct Server {
+    connection: Connection,
+    documents: HashMap<Url, String>,
+    schema: Option<SlopSchema>,
+}
+
+fn main() -> BoxResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(true.into()),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(true.into()),
+        completion_provider: Some(CompletionOptions::default()),
+        definition_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    })?;
+    let params: InitializeParams = serde_json::from_value(connection.initialize(capabilities)?)?;
+
+    let mut server = Server { connection, documents: HashMap::new(), schema: None };
+    server.load_schema(&params);
+
+    // The connection must be dropped before joining, or the writer thread
+    // keeps waiting for messages.
+    server.run()?;
+
+    io_threads.join()?;
+    Ok(())
+}
+
+impl Server {
+    // Loads the schema from the `schema` initialization option, if present.
+    fn load_schema(&mut self, params: &InitializeParams) {
+        let Some(path) = params
+            .initialization_options
+            .as_ref()
+            .and_then(|o| o.get("schema"))
+            .and_then(|s| s.as_str())
+        else {
+            return;
+        };
+
+        let mut path = PathBuf::from(path);
+
+        #[allow(deprecated)]
+        if let Some(root) = params.root_uri.as_ref().and_then(|u| u.to_file_path().ok()) {
+            path = root.join(path);
+        }
+
+        match SlopSchema::open(&path) {
+            Ok(schema) => self.schema = Some(schema),
+            Err(e) => self.notify::<ShowMessage>(ShowMessageParams {
+                typ: MessageType::ERROR,
+                message: format!("slop: couldn't load the schema `{}`: {e}", path.display()),
+            }),
+        }
+    }
+
+    fn run(mut self) -> BoxResult<()> {
+        while let Ok(message) = self.connection.receiver.recv() {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+
+                    let response = self.handle_request(request);
+                    self.connection.sender.send(Message::Response(response))?;
+                },
+                Message::Notification(notification) => self.handle_notification(notification),
+                Message::Response(_) => {},
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let schema = self.schema.as_ref();
+
+        let result = match request.method.as_str() {
+            DocumentSymbolRequest::METHOD => self
+                .params::<DocumentSymbolRequest>(request)
+                .map(|(text, _)| serde_json::to_value(analysis::symbols(text))),
+            FoldingRangeRequest::METHOD => self
+                .params::<FoldingRangeRequest>(request)
+                .map(|(text, _)| serde_json::to_value(analysis::folding_ranges(text))),
+            Formatting::METHOD => self
+                .params::<Formatting>(request)
+                .map(|(text, _)| serde_json::to_value(analysis::formatting(text))),
+            HoverRequest::METHOD => self
+                .params::<HoverRequest>(request)
+                .map(|(text, p)| serde_json::to_value(analysis::hover(
+                    text,
+                    p.text_document_position_params.position,
+                    schema,
+                ))),
+            Completion::METHOD => self
+                .params::<Completion>(request)
+                .map(|(text, p)| serde_json::to_value(analysis::completion(
+                    text,
+                    p.text_document_position.position,
+                    schema,
+                ))),
+            GotoDefinition::METHOD => self
+                .params::<GotoDefinition>(request)
+                .map(|(text, p)| {
+                    let position = p.text_document_position_params;
+                    let uri = position.text_document.uri;
+                    let path = uri.to_file_path().ok();
+                    let definition = analysis::definition(text, path.as_deref(), position.position);
+
+                    serde_json::to_value(definition
+                        .and_then(|(target, range)| Some(Location {
+                            uri: match target {
+                                Some(target) => Url::from_file_path(target).ok()?,
+                                None => uri,
+                            },
+                            range,
+                        }))
+                        .map(GotoDefinitionResponse::Scalar))
+                }),
+            _ => return Response::new_err(
+                id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unsupported request `{}`", request.method),
+            ),
+        };
+
+        match result {
+            Ok(Ok(value)) => Response::new_ok(id, value),
+            Ok(Err(e)) => Response::new_err(id, ErrorCode::InternalError as i32, e.to_string()),
+            Err(message) => Response::new_err(id, ErrorCode::InvalidParams as i32, message),
+        }
+    }
+
+    // Extracts the request's parameters, along with the text of the document
+    // they refer to.
+    fn params<R>(&self, request: Request) -> Result<(&str, R::Params), String>
+    where
+        R: lsp_types::request::Request,
+        R::Params: HasUri,
+    {
+        let params: R::Params = serde_json::from_value(request.params)
+            .map_err(|e| e.to_string())?;
+        let text = self
+            .documents
+            .get(params.uri())
+            .ok_or_else(|| format!("the document `{}` is not open", params.uri()))?;
+
+        Ok((text, params))
+    }
+
+    fn handle_notification(&mut self, notification: Notification) {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Ok(params) = notification.extract::<lsp_types::DidOpenTextDocumentParams>(
+                    DidOpenTextDocument::METHOD,
+                ) else { return };
+                let document = params.text_document;
+                self.documents.insert(document.uri.clone(), document.text);
+                self.publish_diagnostics(document.uri);
+            },
+            DidChangeTextDocument::METHOD => {
+                let Ok(params) = notification.extract::<lsp_types::DidChangeTextDocumentParams>(
+                    DidChangeTextDocument::METHOD,
+                ) else { return };
+
+                // With full sync, the last change holds the whole text.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    let uri = params.text_document.uri;
+                    self.documents.insert(uri.clone(), change.text);
+                    self.publish_diagnostics(uri);
+                }
+            },
+            DidCloseTextDocument::METHOD => {
+                let Ok(params) = notification.extract::<lsp_types::DidCloseTextDocumentParams>(
+                    DidCloseTextDocument::METHOD,
+                ) else { return };
+                self.documents.remove(&params.text_document.uri);
+                self.notify::<PublishDiagnostics>(PublishDiagnosticsParams {
+                    uri: params.text_document.uri,
+                    diagnostics: vec![],
+                    version: None,
+                });
+            },
+            _ => {},
+        }
+    }
+
+    fn publish_diagnostics(&self, uri: Url) {
+        let Some(text) = self.documents.get(&uri) else { return };
+
+        self.notify::<PublishDiagnostics>(PublishDiagnosticsParams {
+            diagnostics: analysis::diagnostics(text, self.schema.as_ref()),
+            uri,
+            version: None,
+        });
+    }
+
+    fn notify<N: lsp_types::notification::Notification>(&self, params: N::Params) {
+        // The client hanging up is handled by the main loop.
+        let _ = self.connection.sender.send(Message::Notification(
+            Notification::new(N::METHOD.to_string(), params),
+        ));
+    }
+}
+
+// Request parameters that refer to a document.
+trait HasUri {
+    fn uri(&self) -> &Url;
+}
+
+macro_rules! impl_has_uri {
+    ($($ty:ty => $($field:ident).+;)*) => {
+        $(impl HasUri for $ty {
+            fn uri(&self) -> &Url {
+                &self.$($field).+.uri
+            }
+        })*
+    };
+}
+
+impl_has_uri! {
+    lsp_types::DocumentSymbolParams => text_document;
+    lsp_types::FoldingRangeParams => text_document;
+    lsp_types::DocumentFormattingParams => text_document;
+    lsp_types::HoverParams => text_document_position_params.text_document;
+    lsp_types::CompletionParams => text_document_position.text_document;
+    lsp_types::GotoDefinitionParams => text_document_position_params.text_document;
+}
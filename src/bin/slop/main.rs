@@ -0,0 +1,350 @@
+//! The `slop` command-line tool. Requires the `cli` feature.
+//!
+//! Edits are made through a [SlopDocument], so comments, blank lines and the
+//! order of the KVs are kept.
+//!
+//! ## Exit codes
+//!
+//! - `0`: Success.
+//! - `1`: The requested key does not exist, `fmt --check` found a file that
+//!   isn't formatted, `lint` found a warning, or `convert --strict` couldn't
+//!   represent a value exactly.
+//! - `2`: The arguments are invalid, including keys and values that can't be
+//!   written to a SLOP file.
+//! - `3`: The file is not a valid SLOP file (or a valid file of the format
+//!   given to `convert`), or `lint` found an error.
+//! - `4`: The file could not be read or written.
+
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use slop_rs::{
+    FormatOptions,
+    LineEnding,
+    RenderOptions,
+    SaveOptions,
+    Severity,
+    Slop,
+    SlopDocument,
+    SlopValue,
+    error::{ErrorKind, SlopError},
+};
+
+const KEY_NOT_FOUND: u8 = 1;
+const CHECK_FAILED: u8 = 1;
+const INVALID_ARGS: u8 = 2;
+const INVALID_SLOP: u8 = 3;
+const IO_ERROR: u8 = 4;
+
+/// Reads and edits SLOP files.
+#[derive(Parser)]
+#[command(name = "slop", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints the value of a KV. List items are printed one per line.
+    Get { file: PathBuf, key: String },
+
+    /// Sets a string KV, creating the KV (and the file) if needed.
+    Set { file: PathBuf, key: String, value: String },
+
+    /// Sets a list KV, creating the KV (and the file) if needed.
+    SetList { file: PathBuf, key: String, items: Vec<String> },
+
+    /// Removes a KV.
+    Rm { file: PathBuf, key: String },
+
+    /// Prints every key, one per line, in the order they appear.
+    Keys { file: PathBuf },
+
+    /// Rewrites files in the canonical style, keeping their comments.
+    Fmt {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Don't write anything; exit with 1 if any file isn't formatted.
+        #[arg(long)]
+        check: bool,
+
+        /// Sort the KVs by key.
+        #[arg(long)]
+        sort_keys: bool,
+
+        /// End lines with `\r\n` instead of `\n`.
+        #[arg(long)]
+        crlf: bool,
+    },
+
+    /// Warns about suspicious constructs, such as `#` after a value.
+    Lint {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Converts a file between SLOP and another format.
+    Convert {
+        file: PathBuf,
+
+        /// Convert from SLOP to this format.
+        #[arg(long, conflicts_with = "from", required_unless_present = "from")]
+        to: Option<Format>,
+
+        /// Convert from this format to SLOP.
+        #[arg(long)]
+        from: Option<Format>,
+
+        /// Write the result to this file instead of the standard output.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Exit with 1 if a value couldn't be represented exactly.
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+    #[cfg(feature = "ini")]
+    Ini,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Get { file, key } => get(&file, &key),
+        Command::Set { file, key, value } => set(&file, key, value.into()),
+        Command::SetList { file, key, items } => set(&file, key, items.into()),
+        Command::Rm { file, key } => rm(&file, &key),
+        Command::Keys { file } => keys(&file),
+        Command::Fmt { files, check, sort_keys, crlf } => {
+            let line_ending = if crlf { LineEnding::CrLf } else { LineEnding::Lf };
+            fmt(&files, check, &FormatOptions { sort_keys, line_ending })
+        },
+        Command::Lint { files } => lint(&files),
+        Command::Convert { file, to, from, output, strict } => {
+            convert(&file, to, from, output.as_deref(), strict)
+        },
+    };
+
+    result.unwrap_or_else(|e| {
+        report(&e);
+        ExitCode::from(match e.kind() {
+            ErrorKind::Io => IO_ERROR,
+            // Keys and values read from a file make the file invalid, not the
+            // arguments.
+            ErrorKind::InvalidKey | ErrorKind::InvalidValue if e.path().is_none() => INVALID_ARGS,
+            _ => INVALID_SLOP,
+        })
+    })
+}
+
+// Prints the error, with the lines it is about if it points into a file.
+fn report(e: &SlopError) {
+    let source = match (e.line(), e.path()) {
+        (Some(_), Some(path)) => read(path).ok(),
+        _ => None,
+    };
+
+    match source {
+        Some(source) => {
+            let options = RenderOptions {
+                color: io::stderr().is_terminal(),
+                ..Default::default()
+            };
+            eprintln!("{}", e.render(&source, None, &options));
+        },
+        None => eprintln!("slop: {e}"),
+    }
+}
+
+fn get(file: &Path, key: &str) -> Result<ExitCode, SlopError> {
+    let doc = SlopDocument::open(file)?;
+
+    match doc.get(key) {
+        Some(SlopValue::String(s)) => println!("{s}"),
+        Some(SlopValue::List(l)) => l.iter().for_each(|i| println!("{i}")),
+        None => return Ok(key_not_found(key)),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn set(file: &Path, key: String, value: SlopValue) -> Result<ExitCode, SlopError> {
+    let mut doc = match SlopDocument::open(file) {
+        Err(e) if e.io_error().is_some_and(|e| e.kind() == io::ErrorKind::NotFound) => {
+            SlopDocument::new()
+        },
+        other => other?,
+    };
+
+    doc.insert(key, value)?;
+    doc.save_atomic(file, &SaveOptions::default())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn rm(file: &Path, key: &str) -> Result<ExitCode, SlopError> {
+    let mut doc = SlopDocument::open(file)?;
+
+    if doc.remove(key).is_none() {
+        return Ok(key_not_found(key));
+    }
+
+    doc.save_atomic(file, &SaveOptions::default())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn keys(file: &Path) -> Result<ExitCode, SlopError> {
+    SlopDocument::open(file)?.keys().for_each(|k| println!("{k}"));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn fmt(files: &[PathBuf], check: bool, options: &FormatOptions)
+    -> Result<ExitCode, SlopError>
+{
+    let mut unformatted = false;
+
+    for file in files {
+        let source = read(file)?;
+        let formatted = slop_rs::format(&source, options).map_err(|e| e.with_path(file))?;
+
+        if formatted == source {
+            continue;
+        }
+
+        if check {
+            eprintln!("slop: `{}` is not formatted", file.display());
+            unformatted = true;
+        } else {
+            write(file, formatted)?;
+        }
+    }
+
+    Ok(if unformatted { ExitCode::from(CHECK_FAILED) } else { ExitCode::SUCCESS })
+}
+
+fn lint(files: &[PathBuf]) -> Result<ExitCode, SlopError> {
+    let mut worst = None;
+
+    for file in files {
+        for lint in slop_rs::lint(&read(file)?) {
+            println!(
+                "{}:{}:{}: {}[{}]: {}",
+                file.display(),
+                lint.line + 1,
+                lint.column + 1,
+                lint.severity(),
+                lint.id,
+                lint.message,
+            );
+            worst = worst.max(Some(lint.severity()));
+        }
+    }
+
+    Ok(match worst {
+        None => ExitCode::SUCCESS,
+        Some(Severity::Warning) => ExitCode::from(CHECK_FAILED),
+        Some(Severity::Error) => ExitCode::from(INVALID_SLOP),
+    })
+}
+
+fn convert(
+    file: &Path,
+    to: Option<Format>,
+    from: Option<Format>,
+    output: Option<&Path>,
+    strict: bool,
+) -> Result<ExitCode, SlopError> {
+    let (result, lossy) = match (to, from) {
+        (Some(format), _) => export(format, &Slop::open(file)?),
+        (_, Some(format)) => {
+            let (slop, lossy) = import(format, &read(file)?).map_err(|e| e.with_path(file))?;
+            let options = FormatOptions { sort_keys: true, ..Default::default() };
+            (slop.to_string_formatted(&options), lossy)
+        },
+        // Clap requires either `--to` or `--from`.
+        (None, None) => unreachable!(),
+    };
+
+    for message in &lossy {
+        eprintln!("slop: warning: {message}");
+    }
+
+    match output {
+        Some(path) => write(path, result)?,
+        None => print!("{result}"),
+    }
+
+    Ok(if strict && !lossy.is_empty() {
+        ExitCode::from(CHECK_FAILED)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+// Returns the converted string and the values that weren't represented exactly.
+fn export(format: Format, slop: &Slop) -> (String, Vec<String>) {
+    #[cfg(any(feature = "toml", feature = "yaml", feature = "ini"))]
+    let report = |c: slop_rs::convert::Conversion<String>| {
+        (c.output, c.lossy.iter().map(|l| l.to_string()).collect())
+    };
+
+    match format {
+        Format::Json => (slop.to_json_string() + "\n", vec![]),
+        #[cfg(feature = "toml")]
+        Format::Toml => report(slop_rs::convert::toml::to_string(slop)),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => report(slop_rs::convert::yaml::to_string(slop)),
+        #[cfg(feature = "ini")]
+        Format::Ini => report(slop_rs::convert::ini::to_string(slop)),
+    }
+}
+
+// Returns the converted Slop and the values that weren't represented exactly.
+fn import(format: Format, source: &str) -> Result<(Slop, Vec<String>), SlopError> {
+    #[cfg(any(feature = "toml", feature = "yaml", feature = "ini"))]
+    let report = |c: slop_rs::convert::Conversion<Slop>| {
+        (c.output, c.lossy.iter().map(|l| l.to_string()).collect())
+    };
+
+    Ok(match format {
+        Format::Json => (Slop::from_json_str(source)?, vec![]),
+        #[cfg(feature = "toml")]
+        Format::Toml => report(slop_rs::convert::toml::from_str(source)?),
+        #[cfg(feature = "yaml")]
+        Format::Yaml => report(slop_rs::convert::yaml::from_str(source)?),
+        #[cfg(feature = "ini")]
+        Format::Ini => report(slop_rs::convert::ini::from_str(source)?),
+    })
+}
+
+// Reads the file the way `Slop::open` does, so UTF-16 and a BOM are handled.
+fn read(file: &Path) -> Result<String, SlopError> {
+    let bytes = fs::read(file).map_err(|e| SlopError::from(e).with_path(file))?;
+    let source = slop_rs::decode(&bytes, false).map_err(|e| e.with_path(file))?;
+    Ok(source.into_owned())
+}
+
+fn write(file: &Path, contents: String) -> Result<(), SlopError> {
+    fs::write(file, contents).map_err(|e| SlopError::from(e).with_path(file))
+}
+
+fn key_not_found(key: &str) -> ExitCode {
+    eprintln!("slop: the key `{key}` does not exist");
+    ExitCode::from(KEY_NOT_FOUND)
+}
@@ -0,0 +1,360 @@
+//! C bindings. Requires the `capi` feature.
+//!
+//! Build the crate as a `cdylib` or a `staticlib`, and include
+//! `include/slop.h`:
+//!
+//! ```sh
+//! cargo rustc --lib --release --features capi --crate-type cdylib
+//! cargo rustc --lib --release --features capi --crate-type staticlib
+//! ```
+//!
+//! The header is generated from this module by cbindgen, and the `capi` test
+//! fails if the committed one is out of date.
+//!
+//! ## Ownership
+//!
+//! - A `Slop *` is owned by the caller, and must be freed exactly once with
+//!   `slop_free`.
+//! - Every `char *` returned by the API is a new copy owned by the caller,
+//!   and must be freed with `slop_string_free`. It stays valid after the
+//!   `Slop *` it came from is changed or freed.
+//! - The API never takes ownership of its arguments: keys, values, paths and
+//!   buffers are copied, and can be freed as soon as the call returns.
+//! - The `message` of a [SlopCError] is owned by the struct, and must be freed
+//!   with `slop_error_free`. A [SlopCError] must be zero-initialized before
+//!   its first use, and can be reused: storing a new error frees the old
+//!   message.
+//!
+//! These rules are repeated at the top of the header.
+//!
+//! ## Conventions
+//!
+//! - Strings are NUL-terminated UTF-8. A key that isn't valid UTF-8 is
+//!   treated as missing.
+//! - Functions that can fail return `NULL` or `false`, and store the error in
+//!   their last argument, unless it is `NULL`.
+//! - Pointers must be valid and not `NULL`, unless stated otherwise.
+//! - A `Slop *` may not be used from more than one thread at a time.
+//!
+//! ```c
+//! SlopCError error = {0};
+//! Slop *slop = slop_open("config.slop", &error);
+//!
+//! if (slop == NULL) {
+//!     fprintf(stderr, "line %zu: %s\n", error.line, error.message);
+//!     slop_error_free(&error);
+//!     return;
+//! }
+//!
+//! char *name = slop_get_string(slop, "name");
+//! // ...
+//! slop_string_free(name);
+//! slop_free(slop);
+//! ```
+
+use std::{
+    ffi::{CStr, CString, c_char},
+    ptr,
+    slice,
+};
+
+use crate::{Slop, SlopValue, error::SlopError, slop::check_exact};
+
+/// An error returned by the C API.
+#[repr(C)]
+#[derive(Debug)]
+pub struct SlopCError {
+    /// A description of the error, or `NULL` if no error was stored. Owned by
+    /// the struct; free it with `slop_error_free`.
+    pub message: *mut c_char,
+
+    /// The 1-based number of the line the error is in, or `0` if the error
+    /// isn't about a line.
+    pub line: usize,
+}
+
+/// Returns a new, empty `Slop *`.
+#[no_mangle]
+pub extern "C" fn slop_new() -> *mut Slop {
+    Box::into_raw(Box::new(Slop::new()))
+}
+
+/// Frees a `Slop *`. Does nothing if `slop` is `NULL`.
+///
+/// ## Safety
+///
+/// `slop` must have been returned by this API, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn slop_free(slop: *mut Slop) {
+    if !slop.is_null() {
+        drop(Box::from_raw(slop));
+    }
+}
+
+/// Opens a SLOP file, resolving its includes. (See [Slop::open])
+///
+/// Returns `NULL` if the file can't be read or parsed.
+///
+/// ## Safety
+///
+/// See the [conventions](self#conventions).
+#[no_mangle]
+pub unsafe extern "C" fn slop_open(path: *const c_char, error: *mut SlopCError) -> *mut Slop {
+    let result = match to_str(path) {
+        Some(path) => Slop::open(path),
+        None => return fail(error, "the path is not valid utf-8".to_string(), None),
+    };
+
+    match result {
+        Ok(slop) => Box::into_raw(Box::new(slop)),
+        Err(e) => fail_with(error, e),
+    }
+}
+
+/// Parses the `len` bytes at `buf` as a SLOP string. The buffer doesn't have
+/// to be NUL-terminated, and may be UTF-8 or UTF-16. (See [Slop::from_bytes])
+///
+/// Returns `NULL` if the buffer is not valid in its encoding or can't be
+/// parsed.
+///
+/// ## Safety
+///
+/// `buf` must point to `len` readable bytes. It may be `NULL` if `len` is `0`.
+#[no_mangle]
+pub unsafe extern "C" fn slop_parse(buf: *const c_char, len: usize, error: *mut SlopCError)
+    -> *mut Slop
+{
+    let bytes = if len == 0 { &[] } else { slice::from_raw_parts(buf.cast::<u8>(), len) };
+
+    match Slop::from_bytes(bytes) {
+        Ok(slop) => Box::into_raw(Box::new(slop)),
+        Err(e) => fail_with(error, e),
+    }
+}
+
+/// Returns a copy of the value of the string KV `key`, or `NULL` if there is
+/// no such KV, or if the value contains a NUL character.
+///
+/// ## Safety
+///
+/// See the [conventions](self#conventions).
+#[no_mangle]
+pub unsafe extern "C" fn slop_get_string(slop: *const Slop, key: *const c_char) -> *mut c_char {
+    match to_str(key).and_then(|key| (*slop).get_string(key)) {
+        Some(value) => to_c_string(value),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Stores the number of items of the list KV `key` in `len`.
+///
+/// Returns `false`, leaving `len` untouched, if there is no such KV.
+///
+/// ## Safety
+///
+/// See the [conventions](self#conventions).
+#[no_mangle]
+pub unsafe extern "C" fn slop_list_len(slop: *const Slop, key: *const c_char, len: *mut usize)
+    -> bool
+{
+    match to_str(key).and_then(|key| (*slop).get_list(key)) {
+        Some(list) => {
+            *len = list.len();
+            true
+        },
+        None => false,
+    }
+}
+
+/// Returns a copy of the item at `index` of the list KV `key`, or `NULL` if
+/// there is no such KV or item, or if the item contains a NUL character.
+///
+/// ## Safety
+///
+/// See the [conventions](self#conventions).
+#[no_mangle]
+pub unsafe extern "C" fn slop_list_item(slop: *const Slop, key: *const c_char, index: usize)
+    -> *mut c_char
+{
+    match to_str(key).and_then(|key| (*slop).get_list(key)?.get(index)) {
+        Some(item) => to_c_string(item),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Inserts a string KV, replacing any KV with the same key.
+/// (See [Slop::insert])
+///
+/// Returns `false` if the key or the value is not valid UTF-8, or if the KV
+/// wouldn't be read back unchanged from a saved file. (See [Slop::validate])
+///
+/// ## Safety
+///
+/// See the [conventions](self#conventions).
+#[no_mangle]
+pub unsafe extern "C" fn slop_insert_string(
+    slop: *mut Slop,
+    key: *const c_char,
+    value: *const c_char,
+    error: *mut SlopCError,
+) -> bool {
+    let Some(value) = to_str(value) else {
+        return fail(error, "the value is not valid utf-8".to_string(), None);
+    };
+
+    insert(slop, key, value.into(), error)
+}
+
+/// Inserts a list KV with the `len` strings at `items`, replacing any KV with
+/// the same key. (See [Slop::insert])
+///
+/// Returns `false` if the key or an item is not valid UTF-8, or if the KV
+/// wouldn't be read back unchanged from a saved file. (See [Slop::validate])
+///
+/// ## Safety
+///
+/// `items` must point to `len` strings. It may be `NULL` if `len` is `0`. See
+/// also the [conventions](self#conventions).
+#[no_mangle]
+pub unsafe extern "C" fn slop_insert_list(
+    slop: *mut Slop,
+    key: *const c_char,
+    items: *const *const c_char,
+    len: usize,
+    error: *mut SlopCError,
+) -> bool {
+    let items = if len == 0 { &[] } else { slice::from_raw_parts(items, len) };
+    let mut list = Vec::with_capacity(len);
+
+    for (i, item) in items.iter().enumerate() {
+        match to_str(*item) {
+            Some(item) => list.push(item.to_string()),
+            None => return fail(error, format!("the item {i} is not valid utf-8"), None),
+        }
+    }
+
+    insert(slop, key, list.into(), error)
+}
+
+/// Removes the KV `key`.
+///
+/// Returns `false` if there is no such KV.
+///
+/// ## Safety
+///
+/// See the [conventions](self#conventions).
+#[no_mangle]
+pub unsafe extern "C" fn slop_remove(slop: *mut Slop, key: *const c_char) -> bool {
+    to_str(key).and_then(|key| (*slop).remove(key)).is_some()
+}
+
+/// Writes the `Slop *` to the file at `path`. If `pretty` is `true`, the
+/// values of lists are indented. (See [Slop::save] and [Slop::save_pretty])
+///
+/// Returns `false` if the file can't be written.
+///
+/// ## Safety
+///
+/// See the [conventions](self#conventions).
+#[no_mangle]
+pub unsafe extern "C" fn slop_save(
+    slop: *const Slop,
+    path: *const c_char,
+    pretty: bool,
+    error: *mut SlopCError,
+) -> bool {
+    let Some(path) = to_str(path) else {
+        return fail(error, "the path is not valid utf-8".to_string(), None);
+    };
+
+    let result = if pretty { (*slop).save_pretty(path) } else { (*slop).save(path) };
+
+    match result {
+        Ok(()) => true,
+        Err(e) => fail_with(error, e),
+    }
+}
+
+/// Frees a string returned by the API. Does nothing if `string` is `NULL`.
+///
+/// ## Safety
+///
+/// `string` must have been returned by this API, and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn slop_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Frees the message of the error, and resets it to `NULL` and line `0`.
+/// Does nothing if `error` is `NULL`.
+///
+/// ## Safety
+///
+/// `error` must be zero-initialized or filled in by this API.
+#[no_mangle]
+pub unsafe extern "C" fn slop_error_free(error: *mut SlopCError) {
+    if let Some(error) = error.as_mut() {
+        slop_string_free(error.message);
+        error.message = ptr::null_mut();
+        error.line = 0;
+    }
+}
+
+unsafe fn insert(slop: *mut Slop, key: *const c_char, value: SlopValue, error: *mut SlopCError)
+    -> bool
+{
+    let Some(key) = to_str(key) else {
+        return fail(error, "the key is not valid utf-8".to_string(), None);
+    };
+
+    // Unlike `Slop::insert`, reject KVs that a saved file would lose.
+    match check_exact(key, &value) {
+        Ok(()) => {
+            (*slop).insert_unchecked(key.to_string(), value);
+            true
+        },
+        Err(e) => fail_with(error, e),
+    }
+}
+
+// Returns the string, or [None] if it isn't valid UTF-8.
+unsafe fn to_str<'a>(s: *const c_char) -> Option<&'a str> {
+    CStr::from_ptr(s).to_str().ok()
+}
+
+fn to_c_string(s: &str) -> *mut c_char {
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+unsafe fn fail_with<T: Failed>(error: *mut SlopCError, e: SlopError) -> T {
+    let line = e.line();
+    fail(error, e.to_string(), line)
+}
+
+// Stores the error, if `error` isn't NULL, and returns the failed value.
+unsafe fn fail<T: Failed>(error: *mut SlopCError, message: String, line: Option<usize>) -> T {
+    if let Some(error) = error.as_mut() {
+        slop_string_free(error.message);
+
+        // The message can't be cut short by a NUL in a key or value.
+        error.message = CString::new(message.replace('\0', "\\0")).unwrap().into_raw();
+        error.line = line.map_or(0, |l| l + 1);
+    }
+
+    T::FAILED
+}
+
+// The value returned by a function that failed.
+trait Failed {
+    const FAILED: Self;
+}
+
+impl Failed for bool {
+    const FAILED: Self = false;
+}
+
+impl Failed for *mut Slop {
+    const FAILED: Self = ptr::null_mut();
+}
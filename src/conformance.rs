@@ -0,0 +1,178 @@
+//! Runs the conformance corpus, which describes the language as a set of
+//! SLOP inputs and their expected results. Requires the `json` feature.
+//!
+//! The corpus lives in the `conformance` directory of the crate, next to a
+//! description of its format, so implementations in other languages can use
+//! it too. Every version of the language has its own directory, such as
+//! `conformance/v1`, where each case is a `<name>.slop` input and either:
+//!
+//! - `<name>.json`, a JSON object with the KVs the input parses into. String
+//!   KVs are strings, and list KVs are arrays of strings.
+//! - `<name>.error`, a SLOP file with the `kind` of the error the input fails
+//!   with (see [ErrorKind::as_str](crate::error::ErrorKind::as_str)) and, if
+//!   the error is about a line, the 1-based `line` it is in.
+//!
+//! Inputs are bytes: they are decoded like [Slop::from_bytes], and
+//! `#!include` directives are not followed.
+//!
+//! ## Examples
+//!
+//! ```
+//! use slop_rs::{Slop, conformance::{self, Outcome}};
+//!
+//! let cases = conformance::load(conformance::corpus_dir()).unwrap();
+//! let failures = conformance::run(&cases, |input| Outcome::of(Slop::from_bytes(input)));
+//!
+//! assert!(failures.is_empty(), "{failures:#?}");
+//! ```
+
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{
+    Slop,
+    error::{SlopError, SlopResult},
+};
+
+/// The version of the language described by [corpus_dir].
+pub const VERSION: u32 = 1;
+
+/// Returns the directory of the corpus for [VERSION], inside the crate's
+/// source.
+pub fn corpus_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("conformance")
+        .join(format!("v{VERSION}"))
+}
+
+/// A case of the corpus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Case {
+    /// The name of the files of the case, without their extension.
+    pub name: String,
+
+    /// The contents of the `.slop` file.
+    pub input: Vec<u8>,
+
+    /// The expected result of parsing [Case::input].
+    pub expected: Outcome,
+}
+
+/// The result of parsing an input, in a form that any implementation can
+/// produce.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The input was parsed. Holds a JSON object with the KVs, like the one
+    /// returned by [Slop::to_json].
+    Kvs(Value),
+
+    /// The input is invalid. Holds the kind of the error (such as
+    /// `invalid-line`), and the 1-based number of the line it is in, if the
+    /// error is about a line.
+    Error(String, Option<usize>),
+}
+
+impl Outcome {
+    /// Returns the outcome of a parse by this crate.
+    pub fn of(result: SlopResult<Slop>) -> Self {
+        match result {
+            Ok(slop) => Outcome::Kvs(slop.to_json()),
+            Err(e) => Outcome::Error(e.kind().as_str().to_string(), e.line().map(|l| l + 1)),
+        }
+    }
+}
+
+/// A case whose actual outcome is not the expected one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    /// The name of the case.
+    pub name: String,
+
+    /// The expected outcome.
+    pub expected: Outcome,
+
+    /// The outcome returned by the implementation.
+    pub actual: Outcome,
+}
+
+/// Loads the cases in the directory, sorted by name.
+///
+/// Returns an [io::ErrorKind::InvalidData] error if a `.slop` file has
+/// neither a `.json` nor an `.error` file, or if an `.error` file is not in
+/// the format described in the [module documentation](self).
+pub fn load<P: AsRef<Path>>(dir: P) -> SlopResult<Vec<Case>> {
+    let mut cases = vec![];
+
+    for entry in fs::read_dir(dir.as_ref()).map_err(|e| SlopError::from(e).with_path(&dir))? {
+        let path = entry?.path();
+
+        if path.extension().is_some_and(|e| e == "slop") {
+            cases.push(load_case(&path)?);
+        }
+    }
+
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+/// Runs every case with `parse`, which should parse the input with the
+/// implementation being tested, and returns the cases that failed.
+pub fn run<F>(cases: &[Case], mut parse: F) -> Vec<Failure>
+where
+    F: FnMut(&[u8]) -> Outcome,
+{
+    cases
+        .iter()
+        .filter_map(|case| {
+            let actual = parse(&case.input);
+
+            (actual != case.expected).then(|| Failure {
+                name: case.name.clone(),
+                expected: case.expected.clone(),
+                actual,
+            })
+        })
+        .collect()
+}
+
+fn load_case(path: &Path) -> SlopResult<Case> {
+    // SAFETY: The path came from `read_dir` and has an extension.
+    let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+    let input = fs::read(path).map_err(|e| SlopError::from(e).with_path(path))?;
+    let json = path.with_extension("json");
+    let error = path.with_extension("error");
+
+    let expected = if json.exists() {
+        let named = |e: SlopError| e.with_path(&json);
+        let contents = fs::read_to_string(&json).map_err(|e| named(e.into()))?;
+        Outcome::Kvs(serde_json::from_str(&contents).map_err(|e| named(e.into()))?)
+    } else if error.exists() {
+        load_error(&Slop::open(&error)?).map_err(|e| e.with_path(&error))?
+    } else {
+        return Err(invalid("has neither a `.json` nor an `.error` file").with_path(path));
+    };
+
+    Ok(Case { name, input, expected })
+}
+
+fn load_error(slop: &Slop) -> SlopResult<Outcome> {
+    let kind = slop
+        .get_string("kind")
+        .ok_or_else(|| invalid("`kind` is missing or not a string kv"))?;
+    let line = match slop.get_string("line") {
+        Some(line) => Some(line.parse().map_err(|_| invalid("`line` is not a number"))?),
+        None => None,
+    };
+
+    Ok(Outcome::Error(kind.clone(), line))
+}
+
+#[inline(always)]
+fn invalid(message: &str) -> SlopError {
+    io::Error::new(io::ErrorKind::InvalidData, message).into()
+}
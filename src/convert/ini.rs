@@ -0,0 +1,161 @@
+//! Conversions between SLOP and INI. Requires the `ini` feature.
+//!
+//! INI only has one level of nesting, so the part of a key before its last
+//! `.` is used as the section name: `server.http.port` is written as `port`
+//! in the `[server.http]` section, and read back the same way.
+//!
+//! INI has no lists either, so list KVs are written as a key that is repeated
+//! once per item. When importing, repeated keys become list KVs.
+
+use ::ini::{EscapePolicy, Ini, Properties};
+
+use crate::{Slop, SlopValue, error::SlopResult};
+
+use super::{Conversion, Importer, LossyValue, Node};
+
+/// Parses an INI string and imports it into a new [Slop].
+///
+/// Keys that appear more than once in the same section become list KVs.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::convert::ini;
+///
+/// let ini_str = "
+/// name = server
+/// [http]
+/// host = a
+/// host = b
+/// ";
+/// let conversion = ini::from_str(ini_str).unwrap();
+/// let slop = &conversion.output;
+///
+/// assert_eq!(slop.get("name"), Some(&"server".into()));
+/// assert_eq!(slop.get("http.host"), Some(&vec!["a", "b"].into()));
+/// assert!(conversion.is_exact());
+/// ```
+pub fn from_str(ini_str: &str) -> SlopResult<Conversion<Slop>> {
+    let ini = Ini::load_from_str(ini_str)?;
+    let mut importer = Importer::default();
+
+    for (section, properties) in &ini {
+        let mut nodes: Vec<(String, Node)> = vec![];
+
+        for (key, value) in properties {
+            let value = Node::String(value.to_string());
+
+            match nodes.iter_mut().find(|(k, _)| k == key) {
+                Some((_, Node::Array(items))) => items.push(value),
+                Some((_, existing)) => {
+                    let first = std::mem::replace(existing, Node::Null);
+                    *existing = Node::Array(vec![first, value]);
+                },
+                None => nodes.push((key.to_string(), value)),
+            }
+        }
+
+        importer.table(section.unwrap_or(""), nodes);
+    }
+
+    Ok(importer.finish())
+}
+
+/// Exports the [Slop] into an INI string.
+///
+/// List KVs with fewer than 2 items can't be read back as lists, INI parsers
+/// remove leading and trailing whitespace from keys, section names and
+/// values, and they remove the quotes around values that start with one, so
+/// these values are listed in [Conversion::lossy]. Empty lists, keys that end
+/// in `.` or start with `[`, and section names that contain `]` are dropped.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{Slop, convert::ini};
+///
+/// let slop: Slop = "name=server\nhttp.host{\na\nb\n}".parse().unwrap();
+/// let conversion = ini::to_string(&slop);
+///
+/// assert_eq!(conversion.output, "name=server\n\n[http]\nhost=a\nhost=b\n");
+/// assert!(conversion.is_exact());
+/// ```
+pub fn to_string(slop: &Slop) -> Conversion<String> {
+    let mut kvs: Vec<_> = slop.iter().collect();
+    kvs.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut ini = Ini::new();
+    let mut lossy = vec![];
+    let mut report = |key: &str, reason: &str| lossy.push(LossyValue {
+        key: key.to_string(),
+        reason: reason.to_string(),
+    });
+
+    for (key, value) in kvs {
+        let (section, name) = match key.rsplit_once('.') {
+            Some((section, name)) => (Some(section.to_string()), name),
+            None => (None, key.as_str()),
+        };
+
+        if name.is_empty() {
+            report(key, "keys that end in `.` can't be represented, so it was dropped");
+            continue;
+        }
+
+        // The line would be read as a section header.
+        if name.starts_with('[') {
+            report(key, "keys that start with `[` can't be represented, so it was dropped");
+            continue;
+        }
+
+        if section.as_ref().is_some_and(|s| s.contains(']')) {
+            report(key, "section names that contain `]` can't be represented, so it was \
+                dropped");
+            continue;
+        }
+
+        if name.trim() != name || section.as_ref().is_some_and(|s| s.trim() != s || s.is_empty()) {
+            report(key, "leading and trailing whitespace in keys and section names, and empty \
+                section names, are removed by ini parsers");
+        }
+
+        let properties = ini.entry(section).or_insert_with(Properties::new);
+
+        match value {
+            SlopValue::String(s) => properties.append(name, s),
+            SlopValue::List(l) => {
+                match l.len() {
+                    0 => report(key, "empty lists can't be represented, so it was dropped"),
+                    1 => report(key, "lists with one item are read back as strings"),
+                    _ => {},
+                }
+                l.iter().for_each(|i| properties.append(name, i));
+            },
+        }
+
+        let values = match value {
+            SlopValue::String(s) => std::slice::from_ref(s),
+            SlopValue::List(l) => &l[..],
+        };
+
+        if values.iter().any(|v| v.trim() != v) {
+            report(key, "leading and trailing whitespace in values is removed by ini \
+                parsers");
+        }
+
+        if values.iter().any(|v| v.starts_with(['"', '\''])) {
+            report(key, "values that start with a quote are unquoted by ini parsers");
+        }
+    }
+
+    let mut output = vec![];
+
+    // Writing into a `Vec` can't fail.
+    ini.write_to_policy(&mut output, EscapePolicy::Reserved).unwrap();
+
+    Conversion {
+        // The writer only writes the strings it was given, so it's valid UTF-8.
+        output: String::from_utf8(output).unwrap(),
+        lossy,
+    }
+}
@@ -0,0 +1,254 @@
+//! Conversions between SLOP and other configuration formats.
+//!
+//! Each format lives in its own submodule, behind a cargo feature of the same
+//! name: [toml](self::toml) (`toml`), [yaml](self::yaml) (`yaml`) and
+//! [ini](self::ini) (`ini`). Every submodule has a `from_str` function, which
+//! imports a string into a [Slop], and a `to_string` function, which exports
+//! a [Slop] into a string.
+//!
+//! Since SLOP has no nesting or types other than strings and lists of strings,
+//! imports follow these rules:
+//!
+//! - Nested tables are flattened into dotted keys. (`[server] port = 80`
+//!   becomes `server.port=80`)
+//! - Arrays of scalars become list KVs, and scalars become string KVs.
+//! - Arrays that contain tables or arrays are flattened into indexed keys.
+//!   (`list.0.name`, `list.1.name`...)
+//!
+//! Exports do the opposite: dotted keys become nested tables, unless a
+//! key can't be nested (such as `a.b` when `a` is also a string KV), in which
+//! case it is written as a single key.
+//!
+//! Every value that couldn't be represented exactly, such as a number that
+//! had to be turned into a string, is listed in [Conversion::lossy].
+
+#[cfg(feature = "ini")]
+pub mod ini;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+
+use std::fmt::Display;
+
+use crate::{
+    Slop,
+    SlopValue,
+    slop::{clean_up_line, is_exact_key},
+};
+
+/// The result of a conversion, along with every value that couldn't be
+/// represented exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conversion<T> {
+    /// The converted value.
+    pub output: T,
+
+    /// The values that were changed or dropped during the conversion.
+    pub lossy: Vec<LossyValue>,
+}
+
+impl<T> Conversion<T> {
+    /// Returns `true` if every value was represented exactly.
+    #[inline(always)]
+    pub fn is_exact(&self) -> bool {
+        self.lossy.is_empty()
+    }
+}
+
+/// A value that couldn't be represented exactly during a conversion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LossyValue {
+    /// The SLOP key of the value, such as `server.port`.
+    /// List items are written as `key[index]`.
+    pub key: String,
+
+    /// What happened to the value.
+    pub reason: String,
+}
+
+impl Display for LossyValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(at `{}`) {}", self.key, self.reason)
+    }
+}
+
+// A format-independent value tree, used as the middle step of conversions.
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    String(String),
+
+    // A non-string scalar, with its text and the name of its type,
+    // such as "an integer".
+    #[cfg_attr(not(any(feature = "toml", feature = "yaml")), allow(dead_code))]
+    Scalar(String, &'static str),
+
+    #[cfg_attr(not(feature = "yaml"), allow(dead_code))]
+    Null,
+
+    Array(Vec<Node>),
+    Table(Vec<(String, Node)>),
+}
+
+// Builds a [Slop] out of imported values, keeping track of lossy values.
+#[derive(Default)]
+pub(crate) struct Importer {
+    slop: Slop,
+    lossy: Vec<LossyValue>,
+}
+
+impl Importer {
+    pub(crate) fn finish(self) -> Conversion<Slop> {
+        Conversion { output: self.slop, lossy: self.lossy }
+    }
+
+    pub(crate) fn report<R: Into<String>>(&mut self, key: &str, reason: R) {
+        self.lossy.push(LossyValue { key: key.to_string(), reason: reason.into() });
+    }
+
+    // Flattens the table's entries into the [Slop], prefixing their keys with
+    // `prefix.` (unless `prefix` is empty).
+    pub(crate) fn table(&mut self, prefix: &str, table: Vec<(String, Node)>) {
+        for (name, node) in table {
+            let key = if prefix.is_empty() { name.clone() } else { format!("{prefix}.{name}") };
+
+            if name.contains('.') {
+                self.report(&key, "the key contains `.`, so it can't be told apart \
+                    from a nested key");
+            }
+
+            self.node(key, node);
+        }
+    }
+
+    fn node(&mut self, key: String, node: Node) {
+        match node {
+            Node::Table(t) => {
+                if t.is_empty() {
+                    self.report(&key, "empty tables can't be represented, so it \
+                        was dropped");
+                }
+                self.table(&key, t);
+            },
+            Node::Array(a) if a.iter().any(|n| matches!(n, Node::Array(_) | Node::Table(_))) => {
+                self.report(&key, "arrays that contain tables or arrays are \
+                    flattened into indexed keys");
+
+                for (i, node) in a.into_iter().enumerate() {
+                    self.node(format!("{key}.{i}"), node);
+                }
+            },
+            Node::Array(a) => {
+                let items = a
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, n)| self.scalar(&format!("{key}[{i}]"), n, true))
+                    .collect::<Vec<_>>();
+                self.insert(key, SlopValue::List(items));
+            },
+            scalar => if let Some(s) = self.scalar(&key, scalar, false) {
+                self.insert(key, SlopValue::String(s));
+            },
+        }
+    }
+
+    // Converts the scalar into a string, or returns [None] if it can't be
+    // written to a SLOP string.
+    fn scalar(&mut self, key: &str, node: Node, is_item: bool) -> Option<String> {
+        let s = match node {
+            Node::String(s) => s,
+            Node::Scalar(s, kind) => {
+                self.report(key, format!("{kind} was converted into a string"));
+                s
+            },
+            Node::Null => {
+                self.report(key, "null was converted into an empty string");
+                String::new()
+            },
+            Node::Array(_) | Node::Table(_) => unreachable!(),
+        };
+
+        if s.contains('\n') || (is_item && clean_up_line(&s) == "}") {
+            self.report(key, "the value can't be written to a slop string, \
+                so it was dropped");
+            return None;
+        }
+
+        if s.ends_with('\r') || (is_item && s.trim_start() != s) {
+            self.report(key, "the value has leading whitespace or a trailing \
+                `\\r`, which will be lost when saved");
+        }
+
+        Some(s)
+    }
+
+    // Inserts the KV, or reports it if the key is not valid.
+    fn insert(&mut self, key: String, value: SlopValue) {
+        if !is_exact_key(&key) {
+            self.report(&key, "the key can't be written to a slop string, \
+                so it was dropped");
+        } else if self.slop.insert_unchecked(key.clone(), value).is_some() {
+            self.report(&key, "the key appears more than once, so only the last \
+                value was kept");
+        }
+    }
+}
+
+// Turns the [Slop]'s dotted keys into nested tables. Keys that can't be nested
+// are kept as they are, at the top level. The keys are sorted.
+#[cfg_attr(not(any(feature = "toml", feature = "yaml")), allow(dead_code))]
+pub(crate) fn unflatten(slop: &Slop) -> Vec<(String, Node)> {
+    let mut kvs: Vec<_> = slop.iter().collect();
+    kvs.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut root = vec![];
+    let mut literal = vec![];
+
+    for (key, value) in kvs {
+        let node = match value {
+            SlopValue::String(s) => Node::String(s.clone()),
+            SlopValue::List(l) => Node::Array(l.iter().cloned().map(Node::String).collect()),
+        };
+        let path: Vec<&str> = key.split('.').collect();
+
+        if path.iter().any(|s| s.is_empty()) {
+            literal.push((key.clone(), node));
+        } else if let Err(node) = insert_path(&mut root, &path, node) {
+            literal.push((key.clone(), node));
+        }
+    }
+
+    root.extend(literal);
+    root
+}
+
+// Inserts the node into nested tables, creating them as needed. Gives the
+// node back if one of the tables along the path is not a table, or if the
+// final key already exists.
+//
+// Since [unflatten] inserts keys in order, a key is always inserted before any
+// other key that it is a prefix of.
+fn insert_path(table: &mut Vec<(String, Node)>, path: &[&str], node: Node)
+    -> Result<(), Node>
+{
+    let Some((first, rest)) = path.split_first() else { return Err(node) };
+    let existing = table.iter().position(|(k, _)| k == first);
+
+    match (existing, rest.is_empty()) {
+        (Some(_), true) => Err(node),
+        (None, true) => {
+            table.push((first.to_string(), node));
+            Ok(())
+        },
+        (Some(i), false) => match &mut table[i].1 {
+            Node::Table(t) => insert_path(t, rest, node),
+            _ => Err(node),
+        },
+        (None, false) => {
+            let mut child = vec![];
+            insert_path(&mut child, rest, node)?;
+            table.push((first.to_string(), Node::Table(child)));
+            Ok(())
+        },
+    }
+}
@@ -0,0 +1,96 @@
+//! Conversions between SLOP and TOML. Requires the `toml` feature.
+//!
+//! See [convert](super) for the conversion rules.
+
+use ::toml::{Table, Value};
+
+use crate::{Slop, error::SlopResult};
+
+use super::{Conversion, Importer, Node, unflatten};
+
+/// Parses a TOML string and imports it into a new [Slop].
+///
+/// Integers, floats, booleans and datetimes are converted into strings, and
+/// are listed in [Conversion::lossy].
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::convert::toml;
+///
+/// let toml_str = "
+///     name = 'server'
+///     [http]
+///     port = 8080
+///     hosts = ['a', 'b']
+/// ";
+/// let conversion = toml::from_str(toml_str).unwrap();
+/// let slop = &conversion.output;
+///
+/// assert_eq!(slop.get("name"), Some(&"server".into()));
+/// assert_eq!(slop.get("http.port"), Some(&"8080".into()));
+/// assert_eq!(slop.get("http.hosts"), Some(&vec!["a", "b"].into()));
+///
+/// // The port was an integer.
+/// assert_eq!(conversion.lossy.len(), 1);
+/// assert_eq!(conversion.lossy[0].key, "http.port");
+/// ```
+pub fn from_str(toml_str: &str) -> SlopResult<Conversion<Slop>> {
+    let table: Table = toml_str.parse()?;
+    let mut importer = Importer::default();
+
+    importer.table("", table_to_nodes(table));
+    Ok(importer.finish())
+}
+
+/// Exports the [Slop] into a TOML string, turning dotted keys into tables.
+///
+/// Every SLOP value can be represented in TOML, so the conversion is
+/// always exact.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{Slop, convert::toml};
+///
+/// let slop: Slop = "name=server\nhttp.port=8080".parse().unwrap();
+/// let conversion = toml::to_string(&slop);
+///
+/// assert_eq!(conversion.output, "name = \"server\"\n\n[http]\nport = \"8080\"\n");
+/// assert!(conversion.is_exact());
+/// ```
+pub fn to_string(slop: &Slop) -> Conversion<String> {
+    Conversion {
+        output: nodes_to_table(unflatten(slop)).to_string(),
+        lossy: vec![],
+    }
+}
+
+fn table_to_nodes(table: Table) -> Vec<(String, Node)> {
+    table.into_iter().map(|(k, v)| (k, value_to_node(v))).collect()
+}
+
+fn value_to_node(value: Value) -> Node {
+    match value {
+        Value::String(s) => Node::String(s),
+        Value::Integer(i) => Node::Scalar(i.to_string(), "an integer"),
+        Value::Float(f) => Node::Scalar(f.to_string(), "a float"),
+        Value::Boolean(b) => Node::Scalar(b.to_string(), "a boolean"),
+        Value::Datetime(d) => Node::Scalar(d.to_string(), "a datetime"),
+        Value::Array(a) => Node::Array(a.into_iter().map(value_to_node).collect()),
+        Value::Table(t) => Node::Table(table_to_nodes(t)),
+    }
+}
+
+fn nodes_to_table(nodes: Vec<(String, Node)>) -> Table {
+    nodes.into_iter().map(|(k, n)| (k, node_to_value(n))).collect()
+}
+
+fn node_to_value(node: Node) -> Value {
+    match node {
+        Node::String(s) | Node::Scalar(s, _) => Value::String(s),
+        Node::Null => Value::String(String::new()),
+        Node::Array(a) => Value::Array(a.into_iter().map(node_to_value).collect()),
+        Node::Table(t) => Value::Table(nodes_to_table(t)),
+    }
+}
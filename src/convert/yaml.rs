@@ -0,0 +1,148 @@
+//! Conversions between SLOP and YAML. Requires the `yaml` feature.
+//!
+//! See [convert](super) for the conversion rules.
+
+use yaml_rust2::{Yaml, YamlEmitter, YamlLoader, yaml::Hash};
+
+use crate::{
+    Slop,
+    error::{SlopError, SlopResult},
+};
+
+use super::{Conversion, Importer, Node, unflatten};
+
+/// Parses a YAML string and imports it into a new [Slop].
+///
+/// The YAML must be a mapping. Integers, floats, booleans and nulls are
+/// converted into strings, and are listed in [Conversion::lossy], along with
+/// any documents after the first one.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::convert::yaml;
+///
+/// let yaml_str = "
+/// name: server
+/// http:
+///   port: 8080
+///   hosts: [a, b]
+/// ";
+/// let conversion = yaml::from_str(yaml_str).unwrap();
+/// let slop = &conversion.output;
+///
+/// assert_eq!(slop.get("name"), Some(&"server".into()));
+/// assert_eq!(slop.get("http.port"), Some(&"8080".into()));
+/// assert_eq!(slop.get("http.hosts"), Some(&vec!["a", "b"].into()));
+///
+/// // The port was an integer.
+/// assert_eq!(conversion.lossy.len(), 1);
+/// assert_eq!(conversion.lossy[0].key, "http.port");
+/// ```
+pub fn from_str(yaml_str: &str) -> SlopResult<Conversion<Slop>> {
+    let docs = YamlLoader::load_from_str(yaml_str)
+        .map_err(|e| SlopError::Yaml { message: e.to_string() })?;
+    let mut importer = Importer::default();
+
+    if docs.len() > 1 {
+        importer.report("", "only the first document was imported");
+    }
+
+    match docs.into_iter().next() {
+        Some(Yaml::Hash(hash)) => {
+            let table = hash_to_nodes(&mut importer, "", hash);
+            importer.table("", table);
+        },
+        None | Some(Yaml::Null) => {},
+        Some(_) => return Err(SlopError::Yaml {
+            message: "the document is not a mapping".to_string(),
+        }),
+    }
+
+    Ok(importer.finish())
+}
+
+/// Exports the [Slop] into a YAML string, turning dotted keys into mappings.
+///
+/// Every SLOP value can be represented in YAML, so the conversion is
+/// always exact.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{Slop, convert::yaml};
+///
+/// let slop: Slop = "name=server\nhttp.port=8080".parse().unwrap();
+/// let conversion = yaml::to_string(&slop);
+///
+/// assert_eq!(conversion.output, "---\nhttp:\n  port: \"8080\"\nname: server\n");
+/// assert!(conversion.is_exact());
+/// ```
+pub fn to_string(slop: &Slop) -> Conversion<String> {
+    let mut output = String::new();
+
+    // Writing into a `String` can't fail.
+    YamlEmitter::new(&mut output)
+        .dump(&node_to_yaml(Node::Table(unflatten(slop))))
+        .unwrap();
+    output.push('\n');
+
+    Conversion { output, lossy: vec![] }
+}
+
+fn hash_to_nodes(importer: &mut Importer, prefix: &str, hash: Hash) -> Vec<(String, Node)> {
+    let mut nodes = vec![];
+
+    for (key, value) in hash {
+        let key = match key {
+            Yaml::String(s) => s,
+            Yaml::Integer(i) => i.to_string(),
+            Yaml::Real(r) => r,
+            Yaml::Boolean(b) => b.to_string(),
+            _ => {
+                importer.report(prefix, "a mapping key is not a scalar, so its value \
+                    was dropped");
+                continue;
+            },
+        };
+        let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+
+        if let Some(node) = yaml_to_node(importer, &path, value) {
+            nodes.push((key, node));
+        }
+    }
+
+    nodes
+}
+
+fn yaml_to_node(importer: &mut Importer, path: &str, yaml: Yaml) -> Option<Node> {
+    Some(match yaml {
+        Yaml::String(s) => Node::String(s),
+        Yaml::Integer(i) => Node::Scalar(i.to_string(), "an integer"),
+        Yaml::Real(r) => Node::Scalar(r, "a float"),
+        Yaml::Boolean(b) => Node::Scalar(b.to_string(), "a boolean"),
+        Yaml::Null => Node::Null,
+        Yaml::Array(a) => Node::Array(a
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, y)| yaml_to_node(importer, &format!("{path}[{i}]"), y))
+            .collect()),
+        Yaml::Hash(h) => Node::Table(hash_to_nodes(importer, path, h)),
+        Yaml::Alias(_) | Yaml::BadValue => {
+            importer.report(path, "the value is not supported, so it was dropped");
+            return None;
+        },
+    })
+}
+
+fn node_to_yaml(node: Node) -> Yaml {
+    match node {
+        Node::String(s) | Node::Scalar(s, _) => Yaml::String(s),
+        Node::Null => Yaml::Null,
+        Node::Array(a) => Yaml::Array(a.into_iter().map(node_to_yaml).collect()),
+        Node::Table(t) => Yaml::Hash(t
+            .into_iter()
+            .map(|(k, n)| (Yaml::String(k), node_to_yaml(n)))
+            .collect()),
+    }
+}
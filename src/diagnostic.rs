@@ -0,0 +1,306 @@
+//! Defines [SlopError::render], which shows an error together with the lines
+//! it is about, like a compiler would.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! The [Display](core::fmt::Display) implementation of [SlopError] only says
+//! which line is wrong. A rendered error looks like this instead:
+//!
+//! ```text
+//! error: `list{` is not closed
+//!  --> config.slop:2:1
+//!   |
+//! 1 | a=1
+//! 2 | list{
+//!   | ^^^^^ the list starts here
+//! 3 |     item
+//!   |         ^ the file ends here
+//!   |
+//!   = help: close the list with a line that only has `}`
+//! ```
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::error::{ErrorKind, SlopError};
+
+/// Options for [SlopError::render].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// If `true`, the output is coloured with ANSI escape codes.
+    ///
+    /// Defaults to `false`.
+    pub color: bool,
+
+    /// How many lines to show before and after each line the error points to.
+    ///
+    /// Defaults to `1`.
+    pub context: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self { color: false, context: 1 }
+    }
+}
+
+impl SlopError {
+    /// Renders the error as plain text, with the lines of `source` it points
+    /// to, a caret under the problem and a hint about how to fix it. (See
+    /// [diagnostic](crate::diagnostic))
+    ///
+    /// `source` should be the string that returned the error, and `name` the
+    /// name shown for it, such as its file path. For
+    /// [SlopError::UnclosedList], both the start of the list and the end of
+    /// the string are shown.
+    ///
+    /// For [SlopError::Named], its name is shown instead of `name`. For
+    /// [SlopError::Include], the file where the error happened is read again
+    /// to show its lines, and `source` is not used.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{RenderOptions, Slop};
+    ///
+    /// let source = "a=1\nnot a kv\nb=2";
+    /// let err = source.parse::<Slop>().unwrap_err();
+    ///
+    /// assert_eq!(err.render(source, Some("config.slop"), &RenderOptions::default()), "\
+    /// error: `not a kv` is not a valid kv
+    ///  --> config.slop:2:1
+    ///   |
+    /// 1 | a=1
+    /// 2 | not a kv
+    ///   | ^^^^^^^^ expected `key=value` or `key{`
+    /// 3 | b=2
+    ///   |
+    ///   = help: string kvs are written as `key=value`, and list kvs start with `key{`");
+    /// ```
+    pub fn render(&self, source: &str, name: Option<&str>, options: &RenderOptions) -> String {
+        if let SlopError::Named { name, error: e } = self {
+            return e.render(source, Some(name), options);
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(rendered) = render_included(self, options) {
+            return rendered;
+        }
+
+        let lines: Vec<&str> = source
+            .lines()
+            .map(|l| l.strip_suffix('\r').unwrap_or(l))
+            .collect();
+
+        Renderer { options, lines, out: vec![] }.render(self, name, &[])
+    }
+}
+
+// Renders the error of an included file with that file's lines.
+#[cfg(feature = "std")]
+fn render_included(error: &SlopError, options: &RenderOptions) -> Option<String> {
+    let SlopError::Include { chain, error: e } = error else { return None };
+    let path = chain.last()?;
+    let source = std::fs::read_to_string(path).ok()?;
+
+    let lines = source.lines().map(|l| l.strip_suffix('\r').unwrap_or(l)).collect();
+    let name = path.display().to_string();
+    let note = format!("included from {}", crate::include::display_chain(chain));
+
+    Some(Renderer { options, lines, out: vec![] }.render(e, Some(&name), &[note]))
+}
+
+// Returns the message of the error, without the line it is in.
+fn headline(error: &SlopError) -> String {
+    match error {
+        SlopError::InvalidLine { text, .. } => format!("`{}` is not a valid kv", text.trim_start()),
+        SlopError::UnclosedList { text, .. } => format!("`{}` is not closed", text.trim_start()),
+        SlopError::InLine { error, .. } => error.to_string(),
+        e => e.to_string(),
+    }
+}
+
+// A message under a part of a line.
+struct Label {
+    line: usize,
+    // The 0-based index of the first character.
+    column: usize,
+    // The number of characters.
+    len: usize,
+    message: &'static str,
+}
+
+impl Label {
+    // Points to the whole line, without its indentation.
+    fn line(lines: &[&str], line: usize, message: &'static str) -> Option<Self> {
+        let text = lines.get(line)?;
+        let trimmed = text.trim_start();
+
+        Some(Self {
+            line,
+            column: text.chars().count() - trimmed.chars().count(),
+            len: trimmed.trim_end().chars().count().max(1),
+            message,
+        })
+    }
+
+    // Points just past the end of the last line.
+    fn end(lines: &[&str], message: &'static str) -> Option<Self> {
+        let line = lines.len().checked_sub(1)?;
+        Some(Self { line, column: lines[line].chars().count(), len: 1, message })
+    }
+}
+
+fn labels(error: &SlopError, lines: &[&str]) -> Vec<Label> {
+    match error {
+        SlopError::InvalidLine { line, .. } => {
+            Label::line(lines, *line, "expected `key=value` or `key{`").into_iter().collect()
+        },
+        SlopError::UnclosedList { line, .. } => {
+            match Label::line(lines, *line, "the list starts here") {
+                Some(start) => [Some(start), Label::end(lines, "the file ends here")]
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+                None => vec![],
+            }
+        },
+        SlopError::InLine { line, error: e, .. } => {
+            let message = match e.kind() {
+                ErrorKind::InvalidKey => "the key can't end in `{`",
+                _ => "this kv can't be parsed",
+            };
+            Label::line(lines, *line, message).into_iter().collect()
+        },
+        _ => vec![],
+    }
+}
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+
+struct Renderer<'a> {
+    options: &'a RenderOptions,
+    lines: Vec<&'a str>,
+    out: Vec<String>,
+}
+
+impl Renderer<'_> {
+    fn render(mut self, error: &SlopError, name: Option<&str>, notes: &[String]) -> String {
+        let labels = labels(error, &self.lines);
+        let shown = self.shown_lines(&labels);
+        let gutter = shown.last().map_or(1, |(_, end)| digits(*end + 1));
+        let pad = " ".repeat(gutter);
+
+        let headline = self.paint(&headline(error), BOLD);
+        self.out.push(format!("{}: {headline}", self.paint("error", RED)));
+
+        if let Some(first) = labels.first() {
+            let location = format!(
+                "{}:{}:{}",
+                name.unwrap_or("<input>"),
+                first.line + 1,
+                first.column + 1,
+            );
+            self.out.push(format!("{pad}{} {location}", self.paint("-->", BLUE)));
+        } else if let Some(name) = name {
+            self.out.push(format!("{pad}{} {name}", self.paint("-->", BLUE)));
+        }
+
+        if !shown.is_empty() {
+            self.out.push(format!("{pad} {}", self.paint("|", BLUE)));
+        }
+
+        for (i, &(start, end)) in shown.iter().enumerate() {
+            if i > 0 {
+                self.out.push(self.paint("...", BLUE));
+            }
+
+            for line in start..=end {
+                self.source_line(line, gutter);
+
+                for label in labels.iter().filter(|l| l.line == line) {
+                    self.label(label, &pad);
+                }
+            }
+        }
+
+        let hints = Some(("help", error.help().to_string()));
+        let notes = notes.iter().map(|n| ("note", n.clone()));
+
+        for (i, (kind, hint)) in hints.into_iter().chain(notes).enumerate() {
+            if i == 0 && !shown.is_empty() {
+                self.out.push(format!("{pad} {}", self.paint("|", BLUE)));
+            }
+
+            let kind = self.paint(kind, BOLD);
+            self.out.push(format!("{pad} {} {kind}: {hint}", self.paint("=", BLUE)));
+        }
+
+        self.out.join("\n")
+    }
+
+    // Returns the ranges of lines to show, with the context around every label.
+    // Ranges that touch are merged.
+    fn shown_lines(&self, labels: &[Label]) -> Vec<(usize, usize)> {
+        let mut ranges: Vec<(usize, usize)> = vec![];
+        let last = self.lines.len().saturating_sub(1);
+
+        for label in labels {
+            let start = label.line.saturating_sub(self.options.context);
+            let end = (label.line + self.options.context).min(last);
+
+            match ranges.last_mut() {
+                Some((_, prev_end)) if start <= *prev_end + 1 => *prev_end = end.max(*prev_end),
+                _ => ranges.push((start, end)),
+            }
+        }
+
+        ranges
+    }
+
+    fn source_line(&mut self, line: usize, gutter: usize) {
+        let number = format!("{:>gutter$}", line + 1);
+        let text = expand_tabs(self.lines[line]);
+        let line = format!("{} {} {text}", self.paint(&number, BLUE), self.paint("|", BLUE));
+        self.out.push(line.trim_end().to_string());
+    }
+
+    fn label(&mut self, label: &Label, pad: &str) {
+        let text = self.lines[label.line];
+        let before: String = text.chars().take(label.column).collect();
+        let covered: String = text.chars().skip(label.column).take(label.len).collect();
+        let spaces = " ".repeat(expand_tabs(&before).chars().count());
+        let carets = "^".repeat(expand_tabs(&covered).chars().count().max(1));
+        let carets = self.paint(&carets, RED);
+
+        self.out.push(format!(
+            "{pad} {} {spaces}{carets} {}",
+            self.paint("|", BLUE),
+            self.paint(label.message, RED),
+        ));
+    }
+
+    fn paint(&self, text: &str, style: &str) -> String {
+        if self.options.color {
+            format!("{style}{text}\x1b[0m")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// Tabs are shown as 4 spaces, so the carets line up in every terminal.
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', "    ")
+}
+
+fn digits(n: usize) -> usize {
+    n.to_string().len()
+}
@@ -0,0 +1,510 @@
+//! Defines the [SlopDocument] struct and its implementations.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+
+use alloc::{
+    collections::BTreeSet,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    str::FromStr,
+    fmt::Display,
+    mem,
+    slice,
+};
+#[cfg(feature = "std")]
+use std::{
+    path::Path,
+    fs,
+};
+
+use crate::{
+    FormatOptions,
+    LineEnding,
+    Slop,
+    SlopValue,
+    encoding::BOM,
+    error::{SlopError, SlopResult},
+    slop::{
+        check_exact,
+        clean_up_line,
+        in_line,
+        is_valid_key,
+        parse_list_kv,
+        parse_string_kv,
+        strip_cr,
+    },
+};
+
+/// A parsed SLOP string that remembers its original layout.
+///
+/// Unlike a [Slop], a [SlopDocument] keeps comments, blank lines, indentation
+/// and the order of its KVs, so it can be edited and written back without
+/// touching the lines that weren't changed.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::SlopDocument;
+///
+/// let mut doc: SlopDocument = "# The answer.\nanswer=41\n".parse().unwrap();
+///
+/// doc.insert("answer".to_string(), "42").unwrap();
+/// doc.insert("question".to_string(), vec!["unknown"]).unwrap();
+///
+/// assert_eq!(
+///     doc.to_string(),
+///     "# The answer.\nanswer=42\nquestion{\n    unknown\n}\n",
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlopDocument {
+    entries: Vec<DocEntry>,
+    line_ending: LineEnding,
+    final_newline: bool,
+}
+
+/// A part of a [SlopDocument].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocEntry {
+    /// A blank line or a comment, stored verbatim, without its line
+    /// terminator.
+    Trivia(String),
+
+    /// A string KV or list KV, along with the lines it was parsed from.
+    Kv(KvEntry),
+}
+
+/// A KV inside of a [SlopDocument].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvEntry {
+    key: String,
+    value: SlopValue,
+    line: Option<usize>,
+    source: Vec<String>,
+}
+
+impl KvEntry {
+    /// Returns the KV's key.
+    #[inline(always)]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns the KV's value.
+    #[inline(always)]
+    pub fn value(&self) -> &SlopValue {
+        &self.value
+    }
+
+    /// Returns the 0-based index of the line the KV starts in,
+    /// or [None] if the KV was inserted after parsing.
+    #[inline(always)]
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    /// Returns the lines that make up the KV, without line terminators.
+    #[inline(always)]
+    pub fn source(&self) -> &[String] {
+        &self.source
+    }
+}
+
+impl SlopDocument {
+    /// Constructs an empty [SlopDocument].
+    pub fn new() -> Self {
+        Self { entries: vec![], line_ending: LineEnding::Lf, final_newline: true }
+    }
+
+    /// Returns the line terminator written between lines.
+    ///
+    /// When parsing, it's the one used by most lines of the string. (See
+    /// [LineEnding::detect]) Lines that used the other one are written with
+    /// this one too.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{LineEnding, SlopDocument};
+    ///
+    /// let mut doc: SlopDocument = "a=1\r\nb=2".parse().unwrap();
+    /// doc.insert("c".to_string(), "3").unwrap();
+    ///
+    /// assert_eq!(doc.line_ending(), LineEnding::CrLf);
+    /// assert!(!doc.final_newline());
+    /// assert_eq!(doc.to_string(), "a=1\r\nb=2\r\nc=3");
+    ///
+    /// doc.set_line_ending(LineEnding::Lf);
+    /// doc.set_final_newline(true);
+    /// assert_eq!(doc.to_string(), "a=1\nb=2\nc=3\n");
+    /// ```
+    #[inline(always)]
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    /// Sets the line terminator written between lines.
+    #[inline(always)]
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) {
+        self.line_ending = line_ending;
+    }
+
+    /// Returns `true` if the last line ends in a line terminator.
+    ///
+    /// When parsing, it's `true` if the string ended in one. Empty
+    /// [SlopDocument]s are always written as an empty string.
+    #[inline(always)]
+    pub fn final_newline(&self) -> bool {
+        self.final_newline
+    }
+
+    /// Sets whether the last line ends in a line terminator.
+    #[inline(always)]
+    pub fn set_final_newline(&mut self, final_newline: bool) {
+        self.final_newline = final_newline;
+    }
+
+    /// Reads the contents of a file and parses it as a SLOP string.
+    #[cfg(feature = "std")]
+    #[inline(always)]
+    pub fn open<P: AsRef<Path>>(path: P) -> SlopResult<Self> {
+        let named = |e: SlopError| e.with_path(&path);
+        let bytes = fs::read(&path).map_err(|e| named(e.into()))?;
+        crate::decode(&bytes, false).and_then(|s| s.parse()).map_err(named)
+    }
+
+    /// Writes the [SlopDocument] to the text file at the provided path.
+    #[cfg(feature = "std")]
+    #[inline(always)]
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> SlopResult<()> {
+        fs::write(&path, self.to_string()).map_err(|e| SlopError::from(e).with_path(path))
+    }
+
+    /// Iterates over the [SlopDocument]'s entries in order.
+    pub fn entries(&self) -> slice::Iter<'_, DocEntry> {
+        self.entries.iter()
+    }
+
+    /// Iterates over the [SlopDocument]'s KVs in order.
+    ///
+    /// If a key appears more than once, all of its KVs are included.
+    pub fn kvs(&self) -> impl Iterator<Item = &KvEntry> {
+        self.entries.iter().filter_map(|e| match e {
+            DocEntry::Kv(kv) => Some(kv),
+            DocEntry::Trivia(_) => None,
+        })
+    }
+
+    /// Iterates over the [SlopDocument]'s keys in the order they first appear.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::SlopDocument;
+    ///
+    /// let doc: SlopDocument = "b=1\na=2\nb=3".parse().unwrap();
+    ///
+    /// assert_eq!(doc.keys().collect::<Vec<_>>(), vec!["b", "a"]);
+    /// ```
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        let mut seen = BTreeSet::new();
+        self.kvs()
+            .map(|kv| kv.key.as_str())
+            .filter(move |k| seen.insert(*k))
+    }
+
+    /// Returns `true` if the [SlopDocument] contains the provided key.
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.kvs().any(|kv| kv.key == key)
+    }
+
+    /// Returns the [SlopValue] associated with the provided key,
+    /// or [None] if no such KV exists.
+    ///
+    /// As with [Slop], if a key appears more than once, the last KV wins.
+    pub fn get(&self, key: &str) -> Option<&SlopValue> {
+        self.kvs().filter(|kv| kv.key == key).last().map(|kv| &kv.value)
+    }
+
+    /// Inserts `value` in the KV defined by `key`.
+    ///
+    /// If the KV already exists, its lines are rewritten in place, keeping
+    /// their indentation. Otherwise, the KV is added to the end of the
+    /// [SlopDocument]. List items are indented with 4 spaces.
+    ///
+    /// Returns the previous value, or [None] if no such KV existed before.
+    ///
+    /// Returns [SlopError::InvalidKey] or [SlopError::InvalidValue] if the KV
+    /// wouldn't be read back unchanged, such as a key that starts with `#` or
+    /// a list item with leading whitespace. (See [Slop::validate])
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{SlopDocument, error::ErrorKind};
+    ///
+    /// let mut doc = SlopDocument::new();
+    /// doc.insert("a".to_string(), "1").unwrap();
+    ///
+    /// let err = doc.insert("#hidden".to_string(), "v").unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidKey);
+    ///
+    /// let err = doc.insert("b".to_string(), vec!["  indented"]).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    /// assert_eq!(doc.to_string(), "a=1\n");
+    /// ```
+    pub fn insert<V: Into<SlopValue>>(&mut self, key: String, value: V)
+        -> SlopResult<Option<SlopValue>>
+    {
+        let value = value.into();
+        check_exact(&key, &value)?;
+
+        let existing = self.entries.iter_mut().rev().find_map(|e| match e {
+            DocEntry::Kv(kv) if kv.key == key => Some(kv),
+            _ => None,
+        });
+
+        if let Some(kv) = existing {
+            let indent = leading_whitespace(&kv.source[0]).to_string();
+            kv.source = write_kv(&indent, &key, &value);
+            return Ok(Some(mem::replace(&mut kv.value, value)));
+        }
+
+        let source = write_kv("", &key, &value);
+        self.entries.push(DocEntry::Kv(KvEntry { key, value, line: None, source }));
+        Ok(None)
+    }
+
+    /// Removes every KV defined by `key`, returning the value that was in
+    /// effect, or [None] if no such KV existed.
+    ///
+    /// Comments and blank lines around the KV are left alone.
+    pub fn remove(&mut self, key: &str) -> Option<SlopValue> {
+        let mut removed = None;
+
+        self.entries.retain(|e| match e {
+            DocEntry::Kv(kv) if kv.key == key => {
+                removed = Some(kv.value.clone());
+                false
+            },
+            _ => true,
+        });
+
+        removed
+    }
+
+    /// Returns the [SlopDocument] as a SLOP string in the canonical style
+    /// described in [FormatOptions], keeping its comments.
+    ///
+    /// The result always parses back into the same KVs.
+    ///
+    /// See also: [format](crate::format)
+    pub fn to_string_formatted(&self, options: &FormatOptions) -> String {
+        let mut header: &[DocEntry] = &[];
+        let mut groups: Vec<(&KvEntry, &[DocEntry])> = vec![];
+        let mut trivia_start = 0;
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let DocEntry::Kv(kv) = entry else { continue };
+            let mut trivia = &self.entries[trivia_start..i];
+
+            if groups.is_empty() {
+                let split = trivia
+                    .iter()
+                    .rposition(|e| matches!(e, DocEntry::Trivia(t) if t.trim().is_empty()))
+                    .map_or(0, |p| p + 1);
+                (header, trivia) = trivia.split_at(split);
+            }
+
+            groups.push((kv, trivia));
+            trivia_start = i + 1;
+        }
+
+        if options.sort_keys {
+            groups.sort_by(|a, b| a.0.key.cmp(&b.0.key));
+        }
+
+        let mut lines: Vec<String> = vec![];
+        push_trivia(&mut lines, header);
+
+        for (kv, trivia) in groups {
+            push_trivia(&mut lines, trivia);
+            lines.extend(write_kv("", &kv.key, &kv.value));
+        }
+
+        push_trivia(&mut lines, &self.entries[trivia_start..]);
+
+        while lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+
+        let line_ending = options.line_ending.as_str();
+        lines.iter().fold(String::new(), |mut acc, l| {
+            acc.push_str(l);
+            acc.push_str(line_ending);
+            acc
+        })
+    }
+
+    /// Returns a [Slop] with the [SlopDocument]'s KVs.
+    pub fn to_slop(&self) -> Slop {
+        let mut slop = Slop::new();
+
+        for kv in self.kvs() {
+            slop.insert_unchecked(kv.key.clone(), kv.value.clone());
+        }
+
+        slop
+    }
+}
+
+impl Default for SlopDocument {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<Slop> for SlopDocument {
+    /// Creates a [SlopDocument] out of the [Slop]'s KVs, in arbitrary order.
+    fn from(slop: Slop) -> Self {
+        let entries = slop
+            .into_iter()
+            .map(|(key, value)| DocEntry::Kv(KvEntry {
+                source: write_kv("", &key, &value),
+                key,
+                value,
+                line: None,
+            }))
+            .collect();
+
+        Self { entries, line_ending: LineEnding::Lf, final_newline: true }
+    }
+}
+
+impl Display for SlopDocument {
+    /// Displays the [SlopDocument] with its original layout, except for the
+    /// KVs that were inserted or changed, using its
+    /// [line ending](SlopDocument::line_ending) and
+    /// [final newline](SlopDocument::final_newline).
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let lines = self.entries.iter().flat_map(|e| match e {
+            DocEntry::Trivia(t) => slice::from_ref(t),
+            DocEntry::Kv(kv) => &kv.source[..],
+        });
+        let line_ending = self.line_ending.as_str();
+
+        for (i, line) in lines.enumerate() {
+            if i > 0 {
+                f.write_str(line_ending)?;
+            }
+            f.write_str(line)?;
+        }
+
+        if self.final_newline && !self.entries.is_empty() {
+            f.write_str(line_ending)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SlopDocument {
+    type Err = SlopError;
+
+    /// Parses a valid SLOP string into a new [SlopDocument].
+    ///
+    /// Accepts the same strings as [Slop::append_slop_string].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix(BOM).unwrap_or(s);
+        let (body, final_newline) = match s.strip_suffix('\n') {
+            Some(body) => (body, true),
+            None => (s, false),
+        };
+        let line_ending = LineEnding::detect(s);
+        let lines: Vec<&str> = if s.is_empty() {
+            vec![]
+        } else {
+            body.split('\n').collect()
+        };
+        let mut entries = vec![];
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = clean_up_line(lines[i]);
+
+            if line.is_empty() || line.starts_with('#') {
+                entries.push(DocEntry::Trivia(strip_cr(lines[i]).to_string()));
+                i += 1;
+                continue;
+            }
+
+            let (key, value, skip) = if let Some((key, value))
+                = parse_string_kv(line)
+            {
+                (key, value, 0)
+            } else if let Some(kv) = parse_list_kv(&lines, i)? {
+                kv
+            } else {
+                return Err(SlopError::InvalidLine { line: i, text: strip_cr(lines[i]).to_string() });
+            };
+
+            if !is_valid_key(key) {
+                return Err(in_line(i, lines[i])(SlopError::InvalidKey { key: key.to_string() }));
+            }
+
+            entries.push(DocEntry::Kv(KvEntry {
+                key: key.to_string(),
+                value,
+                line: Some(i),
+                source: lines[i..=i + skip].iter().map(|l| strip_cr(l).to_string()).collect(),
+            }));
+            i += skip + 1;
+        }
+
+        Ok(Self { entries, line_ending, final_newline })
+    }
+}
+
+// Pushes the trimmed trivia lines, skipping blank lines at the start and
+// blank lines that follow another blank line.
+fn push_trivia(lines: &mut Vec<String>, trivia: &[DocEntry]) {
+    for entry in trivia {
+        if let DocEntry::Trivia(t) = entry {
+            let t = t.trim();
+
+            if !t.is_empty() || lines.last().is_some_and(|l| !l.is_empty()) {
+                lines.push(t.to_string());
+            }
+        }
+    }
+}
+
+// Returns the whitespace at the start of the line.
+#[inline]
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
+// Writes the KV as SLOP lines, with list items indented by 4 spaces relative
+// to `indent`. Empty items are written as empty lines.
+fn write_kv(indent: &str, key: &str, value: &SlopValue) -> Vec<String> {
+    match value {
+        SlopValue::String(s) => vec![format!("{indent}{key}={s}")],
+        SlopValue::List(l) => {
+            let mut lines = vec![format!("{indent}{key}{{")];
+            lines.extend(l.iter().map(|i| if i.is_empty() {
+                String::new()
+            } else {
+                format!("{indent}    {i}")
+            }));
+            lines.push(format!("{indent}}}"));
+            lines
+        },
+    }
+}
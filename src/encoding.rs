@@ -0,0 +1,205 @@
+//! Defines [decode], which turns the bytes of a SLOP file into a string.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! SLOP strings are UTF-8, but files written by some editors start with a
+//! byte order mark (BOM), or are UTF-16. [decode] handles both, and is used by
+//! [Slop::open](crate::Slop::open) and the other functions that read files.
+//! The parsers also skip a BOM at the start of a string.
+//!
+//! Invalid bytes are either an error ([SlopError::InvalidEncoding], which
+//! says where they are), or replaced with `U+FFFD` if decoding lossily.
+
+use alloc::{
+    borrow::Cow,
+    string::String,
+};
+use core::{char, fmt, str};
+
+use crate::{
+    Slop,
+    error::{SlopError, SlopResult},
+};
+
+/// The byte order mark, as a character.
+pub(crate) const BOM: char = '\u{feff}';
+
+/// An encoding detected by [Encoding::detect].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// UTF-8, with or without a BOM.
+    Utf8,
+    /// Little-endian UTF-16.
+    Utf16Le,
+    /// Big-endian UTF-16.
+    Utf16Be,
+}
+
+impl Encoding {
+    /// Detects the encoding of the bytes from their BOM, if they have one.
+    ///
+    /// Without a BOM, the bytes are UTF-16 if one of their first two bytes is
+    /// `0` and the other isn't, as happens when the first character is ASCII.
+    /// Otherwise, they are UTF-8.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Encoding;
+    ///
+    /// assert_eq!(Encoding::detect(b"\xef\xbb\xbfa=1"), Encoding::Utf8);
+    /// assert_eq!(Encoding::detect(b"\xff\xfea\0"), Encoding::Utf16Le);
+    /// assert_eq!(Encoding::detect(b"\0a\0=\0001"), Encoding::Utf16Be);
+    /// assert_eq!(Encoding::detect(b"a=1"), Encoding::Utf8);
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [0xff, 0xfe, ..] => Encoding::Utf16Le,
+            [0xfe, 0xff, ..] => Encoding::Utf16Be,
+            [a, 0, ..] if *a != 0 => Encoding::Utf16Le,
+            [0, b, ..] if *b != 0 => Encoding::Utf16Be,
+            _ => Encoding::Utf8,
+        }
+    }
+
+    /// Returns the name of the encoding, such as `utf-16le`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Decodes the bytes of a SLOP file, in the encoding returned by
+/// [Encoding::detect], and removes the BOM.
+///
+/// Returns [SlopError::InvalidEncoding] at the first invalid byte, or, if
+/// `lossy` is `true`, replaces every invalid sequence with `U+FFFD`.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{decode, error::SlopError};
+///
+/// assert_eq!(decode(b"\xef\xbb\xbfa=1", false).unwrap(), "a=1");
+/// assert_eq!(decode(b"\xff\xfea\0=\x001\0", false).unwrap(), "a=1");
+/// assert_eq!(decode(b"a=1\nb=\xff", true).unwrap(), "a=1\nb=\u{fffd}");
+///
+/// let err = decode(b"a=1\nb=\xff", false).unwrap_err();
+/// assert_eq!(err.to_string(), "(in line 2) the byte 6 is not valid utf-8");
+/// ```
+pub fn decode(bytes: &[u8], lossy: bool) -> SlopResult<Cow<'_, str>> {
+    match Encoding::detect(bytes) {
+        Encoding::Utf8 => {
+            let bom = if bytes.starts_with(b"\xef\xbb\xbf") { 3 } else { 0 };
+            let bytes = &bytes[bom..];
+
+            match str::from_utf8(bytes) {
+                Ok(s) => Ok(Cow::Borrowed(s)),
+                Err(_) if lossy => Ok(String::from_utf8_lossy(bytes)),
+                Err(e) => Err(invalid_utf8(bytes, bom, e.valid_up_to())),
+            }
+        },
+        encoding => decode_utf16(bytes, encoding, lossy).map(Cow::Owned),
+    }
+}
+
+// Returns the error for the invalid UTF-8 at `bytes[valid_up_to]`. `offset` is
+// the position of `bytes` in the input.
+pub(crate) fn invalid_utf8(bytes: &[u8], offset: usize, valid_up_to: usize) -> SlopError {
+    let line = bytes[..valid_up_to].iter().filter(|b| **b == b'\n').count();
+    SlopError::InvalidEncoding { encoding: Encoding::Utf8, line, offset: offset + valid_up_to }
+}
+
+// Decodes a line of UTF-8 input, without its `\n`, and removes the BOM if it's
+// the first line. `offset` is the position of the line in the input.
+#[cfg(feature = "std")]
+pub(crate) fn decode_line(line: &[u8], index: usize, offset: usize, lossy: bool)
+    -> SlopResult<Cow<'_, str>>
+{
+    let bom = if index == 0 && line.starts_with(b"\xef\xbb\xbf") { 3 } else { 0 };
+    let line = &line[bom..];
+
+    match str::from_utf8(line) {
+        Ok(line) => Ok(Cow::Borrowed(line)),
+        Err(_) if lossy => Ok(String::from_utf8_lossy(line)),
+        Err(e) => Err(SlopError::InvalidEncoding {
+            encoding: Encoding::Utf8,
+            line: index,
+            offset: offset + bom + e.valid_up_to(),
+        }),
+    }
+}
+
+fn decode_utf16(bytes: &[u8], encoding: Encoding, lossy: bool) -> SlopResult<String> {
+    let bom = match bytes {
+        [0xff, 0xfe, ..] | [0xfe, 0xff, ..] => 2,
+        _ => 0,
+    };
+    let body = &bytes[bom..];
+    let (whole, odd) = body.split_at(body.len() / 2 * 2);
+    let units = whole.chunks_exact(2).map(|pair| match encoding {
+        Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+        _ => u16::from_be_bytes([pair[0], pair[1]]),
+    });
+
+    let mut decoded = String::with_capacity(body.len() / 2);
+    let mut offset = bom;
+
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => {
+                decoded.push(c);
+                offset += c.len_utf16() * 2;
+            },
+            Err(_) if lossy => {
+                decoded.push(char::REPLACEMENT_CHARACTER);
+                offset += 2;
+            },
+            Err(_) => return Err(invalid_utf16(&decoded, encoding, offset)),
+        }
+    }
+
+    // A byte left over at the end is half a unit.
+    if !odd.is_empty() {
+        if !lossy {
+            return Err(invalid_utf16(&decoded, encoding, offset));
+        }
+
+        decoded.push(char::REPLACEMENT_CHARACTER);
+    }
+
+    Ok(decoded)
+}
+
+fn invalid_utf16(decoded: &str, encoding: Encoding, offset: usize) -> SlopError {
+    let line = decoded.matches('\n').count();
+    SlopError::InvalidEncoding { encoding, line, offset }
+}
+
+impl Slop {
+    /// Decodes the bytes with [decode] (without `lossy`), then parses them as
+    /// a SLOP string.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let slop = Slop::from_bytes(b"\xef\xbb\xbfa=1").unwrap();
+    /// assert_eq!(slop.get("a"), Some(&"1".into()));
+    /// ```
+    #[inline(always)]
+    pub fn from_bytes(bytes: &[u8]) -> SlopResult<Self> {
+        decode(bytes, false)?.parse()
+    }
+}
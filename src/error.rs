@@ -1,37 +1,573 @@
 //! Defines [slop_rs](crate)'s error types.
 
-use std::io;
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use thiserror::Error;
 
+#[cfg(feature = "std")]
+use crate::include::display_chain;
+use crate::{encoding::Encoding, parse::Limit};
+
+// Describes the exceeded limit, such as "(in line 3) the key is longer than
+// 64 bytes".
+fn display_limit(limit: &Limit, line: Option<usize>, max: usize) -> String {
+    let message = match limit {
+        Limit::Bytes => format!("the input is longer than {max} bytes"),
+        Limit::KeyLen => format!("the key is longer than {max} bytes"),
+        Limit::ValueLen => format!("the value is longer than {max} bytes"),
+        Limit::Kvs => format!("there are more than {max} kvs"),
+        Limit::ListItems => format!("the list has more than {max} items"),
+        Limit::ExpandedLen => format!("a value is longer than {max} bytes once expanded"),
+    };
+
+    match line {
+        Some(line) => format!("(in line {}) {message}", line + 1),
+        None => message,
+    }
+}
+
+// Formats a list of keys as "`a` -> `b` -> `c`".
+fn display_keys(keys: &[String]) -> String {
+    keys.iter().map(|k| format!("`{k}`")).collect::<Vec<_>>().join(" -> ")
+}
+
 /// Alias of [Result] where [Err] holds a [SlopError].
 pub type SlopResult<T> = Result<T, SlopError>;
 
 /// The possible errors returned by the SLOP API.
+///
+/// New variants may be added in minor releases, so prefer
+/// [SlopError::kind] and the other accessors ([SlopError::line],
+/// [SlopError::column], [SlopError::path] and [SlopError::io_error]) to
+/// matching on the variants. The variants are `#[non_exhaustive]` too, so
+/// they can gain fields: outside of this crate, they can't be constructed,
+/// and are matched with `..`, as in `SlopError::InvalidKey { key, .. }`.
 /// 
 /// See also: [SlopResult]
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum SlopError {
     /// While parsing, the line was not a valid string KV or list KV starter.
     /// 
-    /// Holds the 0-based index and contents if the line in question.
-    /// The index is written as 1-based when displayed.
-    #[error("(in line {}) `{1}` is not a valid kv", .0 + 1)]
-    InvalidLine(usize, String),
+    /// Holds the 0-based index and contents if the line in question,
+    /// indentation included. The index is written as 1-based when displayed.
+    #[non_exhaustive]
+    #[error("(in line {}) `{}` is not a valid kv", .line + 1, .text.trim_start())]
+    InvalidLine { line: usize, text: String },
 
     /// While parsing, the list KV was never closed.
     /// 
     /// Holds the 0-based index and contents of the line that starts the KV.
     /// The index is written as 1-based when displayed.
-    #[error("(in line {}) `{1}` is not closed", .0 + 1)]
-    UnclosedList(usize, String),
+    #[non_exhaustive]
+    #[error("(in line {}) `{}` is not closed", .line + 1, .text.trim_start())]
+    UnclosedList { line: usize, text: String },
 
     /// Returned during [Slop::insert](crate::Slop::insert) if the key contains
-    /// `=` or ends in `{`.
-    #[error("the key `{0}` contains invalid characters")]
-    InvalidKey(String),
+    /// `=` or ends in `{`, and by [Slop::validate](crate::Slop::validate) and
+    /// [SlopDocument::insert](crate::SlopDocument::insert) if the key wouldn't
+    /// be read back unchanged.
+    #[non_exhaustive]
+    #[error("the key `{key}` contains invalid characters")]
+    InvalidKey { key: String },
+
+    /// Returned when decoding the bytes of a SLOP file if they are not valid in
+    /// their encoding. (See [decode](crate::decode))
+    ///
+    /// Holds the encoding, the 0-based index of the line and the offset of the
+    /// first invalid byte from the start of the input. The index is written as
+    /// 1-based when displayed.
+    #[non_exhaustive]
+    #[error("(in line {}) the byte {} is not valid {}", .line + 1, .offset, .encoding)]
+    InvalidEncoding { encoding: Encoding, line: usize, offset: usize },
+
+    /// Returned by [Slop::parse_with](crate::Slop::parse_with) and the
+    /// functions like it if the input exceeds one of the limits of the
+    /// [ParseOptions](crate::ParseOptions), and by
+    /// [Slop::interpolate](crate::Slop::interpolate) if a value exceeds
+    /// [InterpolateOptions::max_len](crate::InterpolateOptions::max_len).
+    ///
+    /// Holds the limit, the 0-based index of the line where it was exceeded
+    /// (if the limit is about the input), and the maximum. The index is
+    /// written as 1-based when displayed.
+    #[non_exhaustive]
+    #[error("{}", display_limit(.limit, *.line, *.max))]
+    LimitExceeded { limit: Limit, line: Option<usize>, max: usize },
+
+    /// Returned when editing a [SlopDocument](crate::SlopDocument) or by
+    /// [Slop::validate](crate::Slop::validate) if the value wouldn't be read
+    /// back unchanged from the SLOP string.
+    ///
+    /// See also: [SlopValue::is_valid](crate::SlopValue::is_valid)
+    #[non_exhaustive]
+    #[error("the value `{value}` cannot be written to a slop string")]
+    InvalidValue { value: String },
+
+    /// Returned when reading or writing a named source, such as by
+    /// [Slop::open](crate::Slop::open) or [Slop::save](crate::Slop::save).
+    ///
+    /// Holds the name of the source (usually its path) and the error.
+    ///
+    /// See also: [SlopError::with_name]
+    #[non_exhaustive]
+    #[error("(in `{name}`) {error}")]
+    Named { name: String, error: Box<SlopError> },
+
+    /// Returned by the parsers if the KV defined in a line is invalid, such as
+    /// when its key ends in `{`.
+    ///
+    /// Holds the 0-based index and contents of the line, indentation included,
+    /// and the error, such as [SlopError::InvalidKey]. The index is written as
+    /// 1-based when displayed.
+    #[non_exhaustive]
+    #[error("(in line {}) {}", .line + 1, .error)]
+    InLine { line: usize, text: String, error: Box<SlopError> },
+
+    /// Returned by [Slop::open](crate::Slop::open) if a file included by the
+    /// opened file can't be read or parsed.
+    ///
+    /// Holds the chain of includes, from the opened file to the file where the
+    /// error happened, and the error.
+    #[cfg(feature = "std")]
+    #[non_exhaustive]
+    #[error("(in {}) {}", display_chain(.chain), .error)]
+    Include { chain: Vec<PathBuf>, error: Box<SlopError> },
+
+    /// Returned by [Slop::open](crate::Slop::open) if a file includes itself,
+    /// directly or through other files.
+    ///
+    /// Holds the chain of includes, which ends with the file that was
+    /// included again.
+    #[cfg(feature = "std")]
+    #[non_exhaustive]
+    #[error("include cycle: {}", display_chain(.chain))]
+    IncludeCycle { chain: Vec<PathBuf> },
+
+    /// Returned by [Slop::open](crate::Slop::open) if includes are nested more
+    /// deeply than [IncludeOptions::max_depth](crate::IncludeOptions::max_depth).
+    ///
+    /// Holds the maximum depth and the chain of includes that exceeds it.
+    #[cfg(feature = "std")]
+    #[non_exhaustive]
+    #[error("includes are nested more than {} levels deep: {}", .max_depth, display_chain(.chain))]
+    IncludeTooDeep { max_depth: usize, chain: Vec<PathBuf> },
+
+    /// Returned when expanding references if a KV refers to itself, directly
+    /// or through other KVs. (See [interpolate](crate::interpolate))
+    ///
+    /// Holds the keys in the cycle, starting and ending with the same key.
+    #[non_exhaustive]
+    #[error("reference cycle: {}", display_keys(.keys))]
+    ReferenceCycle { keys: Vec<String> },
+
+    /// Returned when expanding references if a reference can't be expanded,
+    /// such as when the key doesn't exist or holds a list.
+    ///
+    /// Holds the key of the KV with the reference and a description of the
+    /// problem.
+    #[non_exhaustive]
+    #[error("(at `{key}`) {problem}")]
+    InvalidReference { key: String, problem: String },
+
+    /// Returned by [Slop::version](crate::Slop::version) if the format version
+    /// is not a whole number. (See [migrate](crate::migrate))
+    ///
+    /// Holds the value of the version KV.
+    #[non_exhaustive]
+    #[error("the format version `{version}` is not a whole number")]
+    InvalidVersion { version: String },
+
+    /// Returned by [Migrator::migrate](crate::Migrator::migrate) if the
+    /// [Slop](crate::Slop) is from a newer format version than the current
+    /// one, which can't be downgraded.
+    ///
+    /// Holds the version of the [Slop](crate::Slop) and the current version.
+    #[non_exhaustive]
+    #[error("the format version {version} is newer than the current version {current}")]
+    UnsupportedVersion { version: u32, current: u32 },
+
+    /// Returned when converting JSON into a [Slop](crate::Slop) if a value is
+    /// not a string or an array of strings, or if the JSON is not an object.
+    ///
+    /// Holds the path of the value (such as `key` or `key[2]`) and the name of
+    /// the JSON type that was found instead.
+    #[cfg(feature = "json")]
+    #[non_exhaustive]
+    #[error("(at `{path}`) found {found}, but only strings and arrays of strings are supported")]
+    UnsupportedJson { path: String, found: &'static str },
+
+    /// Wrapper for [serde_json::Error]s.
+    #[cfg(feature = "json")]
+    #[non_exhaustive]
+    #[error("json error: {error}")]
+    Json {
+        #[from]
+        error: serde_json::Error,
+    },
+
+    /// Returned when loading a [SlopSchema](crate::schema::SlopSchema) from a
+    /// [Slop](crate::Slop) if a KV is not a valid schema property.
+    ///
+    /// Holds the key of the KV and a description of the problem.
+    #[cfg(feature = "schema")]
+    #[non_exhaustive]
+    #[error("(at `{key}`) {problem}")]
+    InvalidSchema { key: String, problem: String },
+
+    /// Wrapper for [toml::de::Error]s.
+    #[cfg(feature = "toml")]
+    #[non_exhaustive]
+    #[error("toml error: {error}")]
+    Toml {
+        #[from]
+        error: toml::de::Error,
+    },
+
+    /// Returned when importing YAML if it can't be parsed, or if it is not
+    /// a mapping.
+    #[cfg(feature = "yaml")]
+    #[non_exhaustive]
+    #[error("yaml error: {message}")]
+    Yaml { message: String },
+
+    /// Wrapper for [ini::ParseError]s.
+    #[cfg(feature = "ini")]
+    #[non_exhaustive]
+    #[error("ini error: {error}")]
+    Ini {
+        #[from]
+        error: ini::ParseError,
+    },
 
     /// Wrapper for [io::Error]s.
-    #[error("io error: {0}")]
-    Io(#[from] io::Error),
+    #[cfg(feature = "std")]
+    #[non_exhaustive]
+    #[error("io error: {error}")]
+    Io {
+        #[from]
+        error: io::Error,
+    },
+}
+
+impl SlopError {
+    /// Returns the kind of the error, looking through
+    /// [SlopError::Named] and [SlopError::Include].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{Slop, error::ErrorKind};
+    ///
+    /// let err = "a=1\nlist{".parse::<Slop>().unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::UnclosedList);
+    /// assert_eq!(err.kind().as_str(), "unclosed-list");
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            SlopError::Named { error, .. } | SlopError::InLine { error, .. } => error.kind(),
+            #[cfg(feature = "std")]
+            SlopError::Include { error, .. } => error.kind(),
+            SlopError::InvalidLine { .. } => ErrorKind::InvalidLine,
+            SlopError::UnclosedList { .. } => ErrorKind::UnclosedList,
+            SlopError::InvalidKey { .. } => ErrorKind::InvalidKey,
+            SlopError::InvalidValue { .. } => ErrorKind::InvalidValue,
+            SlopError::InvalidEncoding { .. } => ErrorKind::InvalidEncoding,
+            SlopError::LimitExceeded { .. } => ErrorKind::LimitExceeded,
+            #[cfg(feature = "std")]
+            SlopError::IncludeCycle { .. } => ErrorKind::IncludeCycle,
+            #[cfg(feature = "std")]
+            SlopError::IncludeTooDeep { .. } => ErrorKind::IncludeTooDeep,
+            SlopError::ReferenceCycle { .. } => ErrorKind::ReferenceCycle,
+            SlopError::InvalidReference { .. } => ErrorKind::InvalidReference,
+            SlopError::InvalidVersion { .. } => ErrorKind::InvalidVersion,
+            SlopError::UnsupportedVersion { .. } => ErrorKind::UnsupportedVersion,
+            #[cfg(feature = "json")]
+            SlopError::UnsupportedJson { .. } => ErrorKind::UnsupportedJson,
+            #[cfg(feature = "json")]
+            SlopError::Json { .. } => ErrorKind::Json,
+            #[cfg(feature = "schema")]
+            SlopError::InvalidSchema { .. } => ErrorKind::InvalidSchema,
+            #[cfg(feature = "toml")]
+            SlopError::Toml { .. } => ErrorKind::Toml,
+            #[cfg(feature = "yaml")]
+            SlopError::Yaml { .. } => ErrorKind::Yaml,
+            #[cfg(feature = "ini")]
+            SlopError::Ini { .. } => ErrorKind::Ini,
+            #[cfg(feature = "std")]
+            SlopError::Io { .. } => ErrorKind::Io,
+        }
+    }
+
+    /// Returns a hint about how to fix the error. (See [ErrorKind::help])
+    #[inline(always)]
+    pub fn help(&self) -> &'static str {
+        self.kind().help()
+    }
+
+    /// Returns the error without the [SlopError::Named], [SlopError::Include]
+    /// and [SlopError::InLine] variants wrapped around it.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{Slop, error::SlopError};
+    ///
+    /// let err = Slop::open("does/not/exist.slop").unwrap_err();
+    /// assert!(matches!(err, SlopError::Named { .. }));
+    /// assert!(matches!(err.root(), SlopError::Io { .. }));
+    /// ```
+    pub fn root(&self) -> &SlopError {
+        match self {
+            SlopError::Named { error, .. } | SlopError::InLine { error, .. } => error.root(),
+            #[cfg(feature = "std")]
+            SlopError::Include { error, .. } => error.root(),
+            e => e,
+        }
+    }
+
+    /// Returns the 0-based index of the line the error is in, if the error is
+    /// about a line.
+    ///
+    /// For [SlopError::Include], the line is in the included file. (See
+    /// [SlopError::path])
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            SlopError::Named { error, .. } => error.line(),
+            #[cfg(feature = "std")]
+            SlopError::Include { error, .. } => error.line(),
+            SlopError::InvalidLine { line, .. }
+            | SlopError::UnclosedList { line, .. }
+            | SlopError::InLine { line, .. }
+            | SlopError::InvalidEncoding { line, .. } => Some(*line),
+            SlopError::LimitExceeded { line, .. } => *line,
+            _ => None,
+        }
+    }
+
+    /// Returns the 0-based index of the character the error starts at in its
+    /// line, if the error is about a line.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let err = "a=1\n    not a kv".parse::<Slop>().unwrap_err();
+    /// assert_eq!(err.line(), Some(1));
+    /// assert_eq!(err.column(), Some(4));
+    ///
+    /// let err = "a=1\n  list{{\n}".parse::<Slop>().unwrap_err();
+    /// assert_eq!(err.to_string(), "(in line 2) the key `list{` contains invalid characters");
+    /// assert_eq!((err.line(), err.column()), (Some(1), Some(2)));
+    /// ```
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            SlopError::Named { error, .. } => error.column(),
+            #[cfg(feature = "std")]
+            SlopError::Include { error, .. } => error.column(),
+            SlopError::InvalidLine { text, .. }
+            | SlopError::UnclosedList { text, .. }
+            | SlopError::InLine { text, .. } => {
+                Some(text.chars().count() - text.trim_start().chars().count())
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the path of the file the error is in, if it is known.
+    ///
+    /// That is the name of a [SlopError::Named], the last file of the chain of
+    /// a [SlopError::Include], or the file with the offending `#!include`
+    /// directive of a [SlopError::IncludeCycle] or
+    /// [SlopError::IncludeTooDeep].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use slop_rs::Slop;
+    ///
+    /// let err = Slop::open("does/not/exist.slop").unwrap_err();
+    /// assert_eq!(err.path(), Some(Path::new("does/not/exist.slop")));
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            SlopError::Named { name, .. } => Some(Path::new(name)),
+            SlopError::Include { chain, .. } => chain.last().map(PathBuf::as_path),
+            SlopError::IncludeCycle { chain } | SlopError::IncludeTooDeep { chain, .. } => {
+                chain.iter().nth_back(1).map(PathBuf::as_path)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the [io::Error] the error was caused by, looking through
+    /// [SlopError::Named], [SlopError::Include] and [SlopError::InLine], or
+    /// [None] if it wasn't caused by one.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::io;
+    /// use slop_rs::Slop;
+    ///
+    /// let err = Slop::open("does/not/exist.slop").unwrap_err();
+    /// assert_eq!(err.io_error().map(|e| e.kind()), Some(io::ErrorKind::NotFound));
+    ///
+    /// assert!("a{{\n}".parse::<Slop>().unwrap_err().io_error().is_none());
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self.root() {
+            SlopError::Io { error } => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Wraps the error in a [SlopError::Named], unless it already says which
+    /// source it is in.
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        match self {
+            e @ SlopError::Named { .. } => e,
+            #[cfg(feature = "std")]
+            e @ (SlopError::Include { .. }
+                | SlopError::IncludeCycle { .. }
+                | SlopError::IncludeTooDeep { .. }) => e,
+            e => SlopError::Named { name: name.into(), error: Box::new(e) },
+        }
+    }
+
+    /// Same as [SlopError::with_name], with the path as the name.
+    #[cfg(feature = "std")]
+    #[inline(always)]
+    pub fn with_path(self, path: impl AsRef<Path>) -> Self {
+        self.with_name(path.as_ref().display().to_string())
+    }
+}
+
+/// The kind of a [SlopError], without its details. (See [SlopError::kind])
+///
+/// Every kind exists regardless of the enabled features, so that matching on
+/// it works the same in every build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// See [SlopError::InvalidLine].
+    InvalidLine,
+    /// See [SlopError::UnclosedList].
+    UnclosedList,
+    /// See [SlopError::InvalidKey].
+    InvalidKey,
+    /// See [SlopError::InvalidValue].
+    InvalidValue,
+    /// See [SlopError::InvalidEncoding].
+    InvalidEncoding,
+    /// See [SlopError::LimitExceeded].
+    LimitExceeded,
+    /// See `SlopError::IncludeCycle`. Requires the `std` feature.
+    IncludeCycle,
+    /// See `SlopError::IncludeTooDeep`. Requires the `std` feature.
+    IncludeTooDeep,
+    /// See [SlopError::ReferenceCycle].
+    ReferenceCycle,
+    /// See [SlopError::InvalidReference].
+    InvalidReference,
+    /// See [SlopError::InvalidVersion].
+    InvalidVersion,
+    /// See [SlopError::UnsupportedVersion].
+    UnsupportedVersion,
+    /// See `SlopError::UnsupportedJson`. Requires the `json` feature.
+    UnsupportedJson,
+    /// See `SlopError::Json`. Requires the `json` feature.
+    Json,
+    /// See `SlopError::InvalidSchema`. Requires the `schema` feature.
+    InvalidSchema,
+    /// See `SlopError::Toml`. Requires the `toml` feature.
+    Toml,
+    /// See `SlopError::Yaml`. Requires the `yaml` feature.
+    Yaml,
+    /// See `SlopError::Ini`. Requires the `ini` feature.
+    Ini,
+    /// See `SlopError::Io`. Requires the `std` feature.
+    Io,
+}
+
+impl ErrorKind {
+    /// Returns the name of the kind in kebab-case, such as `invalid-line`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::InvalidLine => "invalid-line",
+            ErrorKind::UnclosedList => "unclosed-list",
+            ErrorKind::InvalidKey => "invalid-key",
+            ErrorKind::InvalidValue => "invalid-value",
+            ErrorKind::InvalidEncoding => "invalid-encoding",
+            ErrorKind::LimitExceeded => "limit-exceeded",
+            ErrorKind::IncludeCycle => "include-cycle",
+            ErrorKind::IncludeTooDeep => "include-too-deep",
+            ErrorKind::ReferenceCycle => "reference-cycle",
+            ErrorKind::InvalidReference => "invalid-reference",
+            ErrorKind::InvalidVersion => "invalid-version",
+            ErrorKind::UnsupportedVersion => "unsupported-version",
+            ErrorKind::UnsupportedJson => "unsupported-json",
+            ErrorKind::Json => "json",
+            ErrorKind::InvalidSchema => "invalid-schema",
+            ErrorKind::Toml => "toml",
+            ErrorKind::Yaml => "yaml",
+            ErrorKind::Ini => "ini",
+            ErrorKind::Io => "io",
+        }
+    }
+
+    /// Returns a hint about how to fix an error of this kind.
+    pub fn help(self) -> &'static str {
+        match self {
+            ErrorKind::InvalidLine => {
+                "string kvs are written as `key=value`, and list kvs start with `key{`"
+            },
+            ErrorKind::UnclosedList => "close the list with a line that only has `}`",
+            ErrorKind::InvalidKey => {
+                "keys can't contain `=` or line breaks, start with whitespace or `#`, or end in `{`"
+            },
+            ErrorKind::InvalidValue => {
+                "values can't contain line breaks or end in `\\r`, and list items can't start \
+                with whitespace or be `}`"
+            },
+            ErrorKind::InvalidEncoding => {
+                "save the file as utf-8, or decode it lossily to replace the invalid bytes"
+            },
+            ErrorKind::LimitExceeded => {
+                "raise the limit in the options, or make the input smaller"
+            },
+            ErrorKind::IncludeCycle => "remove one of the `#!include` directives in the cycle",
+            ErrorKind::IncludeTooDeep => "include fewer files, or raise the maximum depth",
+            ErrorKind::ReferenceCycle => "one of the values in the cycle must not refer to a key",
+            ErrorKind::InvalidReference => {
+                "references can only point to string kvs; write `$${` for a literal `${`"
+            },
+            ErrorKind::InvalidVersion => {
+                "write the version as a whole number, such as `format-version=2`"
+            },
+            ErrorKind::UnsupportedVersion => "the file was saved by a newer version of the program",
+            ErrorKind::UnsupportedJson => "slop values can only be strings or lists of strings",
+            ErrorKind::Json => "check that the string is valid json",
+            ErrorKind::InvalidSchema => "check the property against the `schema` module docs",
+            ErrorKind::Toml => "check that the string is valid toml",
+            ErrorKind::Yaml => "check that the string is a valid yaml mapping",
+            ErrorKind::Ini => "check that the string is valid ini",
+            ErrorKind::Io => "check that the file exists and that it can be read and written",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
@@ -0,0 +1,97 @@
+//! Defines the canonical SLOP formatter and its options.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+
+use alloc::string::String;
+
+use crate::{SlopDocument, error::SlopResult};
+
+/// The line terminator written by the formatter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// `\n`, the default.
+    #[default]
+    Lf,
+
+    /// `\r\n`.
+    CrLf,
+}
+
+impl LineEnding {
+    /// Returns the line terminator used by most lines of the string, or
+    /// [LineEnding::Lf] if there is a tie.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::LineEnding;
+    ///
+    /// assert_eq!(LineEnding::detect("a=1\r\nb=2\r\nc=3\n"), LineEnding::CrLf);
+    /// assert_eq!(LineEnding::detect("a=1\r\nb=2\n"), LineEnding::Lf);
+    /// assert_eq!(LineEnding::detect("a=1"), LineEnding::Lf);
+    /// ```
+    pub fn detect(s: &str) -> Self {
+        let (crlf, lf) = s.match_indices('\n').fold((0, 0), |(crlf, lf), (i, _)| {
+            if s[..i].ends_with('\r') { (crlf + 1, lf) } else { (crlf, lf + 1) }
+        });
+
+        if crlf > lf { Self::CrLf } else { Self::Lf }
+    }
+
+    /// Returns the line terminator as a string.
+    #[inline(always)]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Options for [format] and the `to_string_formatted` methods.
+///
+/// The canonical style is:
+///
+/// - Keys, comments and closing brackets start at column 1.
+/// - List items are indented with 4 spaces.
+/// - Trailing whitespace is removed from comments and blank lines. (It is
+///   significant everywhere else, so it is kept.)
+/// - Runs of blank lines are collapsed into one, and blank lines at the start
+///   and end of the string are removed.
+/// - Every line, including the last one, ends in [FormatOptions::line_ending].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Whether to sort the KVs by key.
+    ///
+    /// Comments and blank lines directly above a KV move along with it.
+    /// Comments at the start of the string that are followed by a blank line
+    /// stay at the top.
+    pub sort_keys: bool,
+
+    /// The line terminator to use.
+    pub line_ending: LineEnding,
+}
+
+/// Parses a SLOP string and returns it in the canonical style, keeping
+/// its comments.
+///
+/// This is a shorthand for [SlopDocument::to_string_formatted].
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{FormatOptions, format};
+///
+/// let slop_str = "  b=2  \n\n\n  #  first  \n  a{\n  x\n    y\n  }\n";
+/// let options = FormatOptions { sort_keys: true, ..Default::default() };
+///
+/// assert_eq!(
+///     format(slop_str, &options).unwrap(),
+///     "#  first\na{\n    x\n    y\n}\nb=2  \n",
+/// );
+/// ```
+#[inline]
+pub fn format(slop_str: &str, options: &FormatOptions) -> SlopResult<String> {
+    Ok(slop_str.parse::<SlopDocument>()?.to_string_formatted(options))
+}
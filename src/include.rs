@@ -0,0 +1,316 @@
+//! Defines the `#!include` directive, which splits a SLOP file into several.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! A line such as `#!include other.slop` is a comment to the parser, so SLOP
+//! strings that use it are still valid. [Slop::open] leaves it at that, since
+//! following includes lets a file read others. [Slop::open_with] resolves it
+//! by parsing the included file in its place, so its KVs override the ones
+//! above the directive and are overridden by the ones below it. Paths are
+//! relative to the including file.
+//!
+//! Inside list KVs, the directive is just another item. [SlopDocument]s
+//! (and so, [format](crate::format)) keep the directive as a comment.
+//!
+//! [SlopDocument]: crate::SlopDocument
+
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    Slop,
+    encoding::decode,
+    error::{SlopError, SlopResult},
+    slop::{clean_up_line, parse_include},
+};
+
+/// Returns the path named by the line if it is an `#!include` directive, or
+/// [None] if it isn't one. Leading whitespace and a trailing `\r` are ignored,
+/// as in the parser.
+///
+/// Only lines outside of list KVs are directives, which this function can't
+/// tell on its own.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::include::directive;
+///
+/// assert_eq!(directive("#!include mods/base.slop"), Some("mods/base.slop"));
+/// assert_eq!(directive("  #!include\tother.slop \r"), Some("other.slop"));
+/// assert_eq!(directive("#!includes.slop"), None);
+/// assert_eq!(directive("# a comment"), None);
+/// ```
+#[inline(always)]
+pub fn directive(line: &str) -> Option<&str> {
+    parse_include(clean_up_line(line))
+}
+
+/// Finds and reads the files named by `#!include` directives.
+///
+/// Implement this trait to load included files from somewhere other than the
+/// filesystem. See [FsResolver] and [MemoryResolver].
+pub trait IncludeResolver {
+    /// Returns the path of the file included as `path` by the file at
+    /// `including`, or of the file that was opened if `including` is [None].
+    ///
+    /// The returned path is used to detect cycles, so it must be the same
+    /// for every way of writing the path of a file.
+    fn resolve(&self, including: Option<&Path>, path: &Path) -> io::Result<PathBuf>;
+
+    /// Returns the contents of a file at a path returned by
+    /// [IncludeResolver::resolve].
+    fn read(&self, path: &Path) -> io::Result<String>;
+
+    /// Returns the bytes of a file at a path returned by
+    /// [IncludeResolver::resolve], which are then decoded with
+    /// [decode](crate::decode).
+    ///
+    /// Defaults to the bytes of [IncludeResolver::read]. Implement it to read
+    /// files that aren't UTF-8.
+    fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.read(path).map(String::into_bytes)
+    }
+}
+
+/// Resolves includes in the filesystem.
+///
+/// Any path is allowed, including absolute ones and ones that go up with
+/// `..`, so only use it on files you trust.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsResolver;
+
+impl IncludeResolver for FsResolver {
+    fn resolve(&self, including: Option<&Path>, path: &Path) -> io::Result<PathBuf> {
+        match including.and_then(Path::parent) {
+            Some(dir) => fs::canonicalize(dir.join(path)),
+            None => fs::canonicalize(path),
+        }
+    }
+
+    #[inline(always)]
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    #[inline(always)]
+    fn read_bytes(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+}
+
+/// Resolves includes from a set of in-memory files.
+///
+/// Paths are joined and normalized without touching the filesystem, so
+/// `a/../b.slop` and `b.slop` are the same file.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{IncludeOptions, MemoryResolver, Slop};
+///
+/// let mut files = MemoryResolver::new();
+/// files.insert("main.slop", "#!include mods/base.slop\nname=main");
+/// files.insert("mods/base.slop", "name=base\nversion=1");
+///
+/// let slop = Slop::open_with("main.slop", &files, &IncludeOptions::default()).unwrap();
+///
+/// assert_eq!(slop.get("name"), Some(&"main".into()));
+/// assert_eq!(slop.get("version"), Some(&"1".into()));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: HashMap<PathBuf, String>,
+}
+
+impl MemoryResolver {
+    /// Constructs a [MemoryResolver] without any files.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file, replacing any file that had the same path.
+    pub fn insert<P: AsRef<Path>, S: Into<String>>(&mut self, path: P, contents: S) {
+        self.files.insert(normalize(path.as_ref()), contents.into());
+    }
+}
+
+impl IncludeResolver for MemoryResolver {
+    fn resolve(&self, including: Option<&Path>, path: &Path) -> io::Result<PathBuf> {
+        Ok(match including.and_then(Path::parent) {
+            Some(dir) => normalize(&dir.join(path)),
+            None => normalize(path),
+        })
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        self.files.get(path).cloned().ok_or_else(|| io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("`{}` does not exist", path.display()),
+        ))
+    }
+}
+
+/// Options for resolving `#!include` directives. See [Slop::open_with].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludeOptions {
+    /// How many levels of includes can be nested inside the file that was
+    /// opened. `0` makes every include an error.
+    ///
+    /// Defaults to `16`.
+    pub max_depth: usize,
+
+    /// If `true`, bytes that aren't valid in the encoding of their file are
+    /// replaced with `U+FFFD`, instead of returning
+    /// [SlopError::InvalidEncoding]. (See [decode](crate::decode))
+    ///
+    /// Defaults to `false`.
+    pub lossy: bool,
+}
+
+impl Default for IncludeOptions {
+    fn default() -> Self {
+        Self { max_depth: 16, lossy: false }
+    }
+}
+
+impl Slop {
+    /// Same as [Slop::open], but follows `#!include` directives, reading the
+    /// file and the files it includes with the provided resolver.
+    ///
+    /// Errors in the file at `path` are returned as they are. Errors in
+    /// included files are wrapped in [SlopError::Include], along with the
+    /// chain of files that led to them.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{IncludeOptions, MemoryResolver, Slop, error::SlopError};
+    ///
+    /// let mut files = MemoryResolver::new();
+    /// files.insert("a.slop", "#!include b.slop");
+    /// files.insert("b.slop", "#!include a.slop");
+    ///
+    /// let options = IncludeOptions::default();
+    /// let err = Slop::open_with("a.slop", &files, &options).unwrap_err();
+    ///
+    /// assert!(matches!(err, SlopError::IncludeCycle { .. }));
+    /// assert_eq!(err.to_string(), "include cycle: `a.slop` -> `b.slop` -> `a.slop`");
+    /// ```
+    pub fn open_with<P, R>(path: P, resolver: &R, options: &IncludeOptions) -> SlopResult<Self>
+    where
+        P: AsRef<Path>,
+        R: IncludeResolver + ?Sized,
+    {
+        let named = |e: SlopError| e.with_path(&path);
+        let resolved = resolver.resolve(None, path.as_ref()).map_err(|e| named(e.into()))?;
+        let mut slop = Slop::new();
+        let mut loader = Loader { resolver, options, chain: vec![resolved] };
+
+        let bytes = resolver.read_bytes(&loader.chain[0]).map_err(|e| named(e.into()))?;
+        let slop_str = decode(&bytes, options.lossy).map_err(named)?;
+        slop.append_with_includes(&slop_str, &mut |slop, include| loader.include(slop, include))
+            .map_err(named)?;
+
+        Ok(slop)
+    }
+}
+
+// Keeps track of the files being loaded, from the one that was opened to the
+// one being parsed.
+struct Loader<'a, R: ?Sized> {
+    resolver: &'a R,
+    options: &'a IncludeOptions,
+    chain: Vec<PathBuf>,
+}
+
+impl<R: IncludeResolver + ?Sized> Loader<'_, R> {
+    fn include(&mut self, slop: &mut Slop, include: &str) -> SlopResult<()> {
+        // SAFETY: The chain always starts with the file that was opened.
+        let including = self.chain.last().unwrap();
+        let path = self.resolver.resolve(Some(including), Path::new(include));
+
+        let path = match path {
+            Ok(path) => path,
+            Err(e) => return Err(self.error(PathBuf::from(include), e.into())),
+        };
+
+        if self.chain.contains(&path) {
+            let mut chain = self.chain.clone();
+            chain.push(path);
+            return Err(SlopError::IncludeCycle { chain });
+        }
+
+        if self.chain.len() > self.options.max_depth {
+            let mut chain = self.chain.clone();
+            chain.push(path);
+            return Err(SlopError::IncludeTooDeep { max_depth: self.options.max_depth, chain });
+        }
+
+        let bytes = match self.resolver.read_bytes(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(self.error(path, e.into())),
+        };
+        let slop_str = match decode(&bytes, self.options.lossy) {
+            Ok(s) => s,
+            Err(e) => return Err(self.error(path, e)),
+        };
+
+        self.chain.push(path);
+        let result = slop.append_with_includes(&slop_str, &mut |slop, include| {
+            self.include(slop, include)
+        });
+        let path = self.chain.pop().unwrap();
+
+        match result {
+            Err(e @ (
+                SlopError::Include { .. }
+                | SlopError::IncludeCycle { .. }
+                | SlopError::IncludeTooDeep { .. }
+            )) => Err(e),
+            Err(e) => Err(self.error(path, e)),
+            Ok(()) => Ok(()),
+        }
+    }
+
+    // Wraps an error that happened in the file at `path`, which is included
+    // by the last file in the chain.
+    fn error(&self, path: PathBuf, error: SlopError) -> SlopError {
+        let mut chain = self.chain.clone();
+        chain.push(path);
+        SlopError::Include { chain, error: Box::new(error) }
+    }
+}
+
+// Removes `.` and resolves `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => { normalized.pop(); },
+                Some(Component::RootDir | Component::Prefix(_)) => {},
+                _ => normalized.push(".."),
+            },
+            c => normalized.push(c),
+        }
+    }
+
+    normalized
+}
+
+// Formats an include chain as "`a` -> `b` -> `c`".
+pub(crate) fn display_chain(chain: &[PathBuf]) -> String {
+    chain
+        .iter()
+        .map(|p| format!("`{}`", p.display()))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
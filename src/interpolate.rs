@@ -0,0 +1,257 @@
+//! Defines the expansion of `${key}` references inside values.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! References are only expanded when asked to, with [Slop::interpolate] or
+//! [Slop::interpolated]. The rules are:
+//!
+//! - `${key}` is replaced by the value of the string KV `key`, after
+//!   expanding its own references.
+//! - `${env:NAME}` is replaced by the environment variable `NAME`, if
+//!   [InterpolateOptions::env] is set. Otherwise, it refers to the key
+//!   `env:NAME`. This option needs the `std` feature.
+//! - `$${` is replaced by `${`, without starting a reference.
+//! - Any other `$` is left as it is.
+//!
+//! References work in string values and list items, but can only point to
+//! string KVs.
+//!
+//! A value can refer to the same key many times, so a few short KVs can expand
+//! to a huge value (`a=${b}${b}`, `b=${c}${c}`, and so on).
+//! [InterpolateOptions::max_len] stops that from using up the memory.
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use std::env;
+
+use crate::{
+    Slop,
+    SlopValue,
+    error::{SlopError, SlopResult},
+    parse::Limit,
+};
+
+/// Options for expanding references. See [Slop::interpolate].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterpolateOptions {
+    /// If `true`, `${env:NAME}` is replaced by the environment variable
+    /// `NAME`.
+    ///
+    /// Defaults to `false`. Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub env: bool,
+
+    /// The maximum length of an expanded value, or of an expanded list item,
+    /// in bytes. [None] means unlimited.
+    ///
+    /// Defaults to 1 MiB.
+    pub max_len: Option<usize>,
+}
+
+impl Default for InterpolateOptions {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            env: false,
+            max_len: Some(1024 * 1024),
+        }
+    }
+}
+
+impl Slop {
+    /// Expands the references in every value of the [Slop]. (See
+    /// [interpolate](crate::interpolate))
+    ///
+    /// Returns [SlopError::ReferenceCycle] if a value refers to itself,
+    /// directly or through other KVs, [SlopError::InvalidReference] if a
+    /// reference can't be expanded, and [SlopError::LimitExceeded] if a value
+    /// is longer than [InterpolateOptions::max_len] once expanded. If an error
+    /// is returned, the [Slop] is left unchanged.
+    ///
+    /// See also: [Slop::interpolated]
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{InterpolateOptions, Slop};
+    ///
+    /// let slop_str = "\
+    ///     root=/opt/game
+    ///     mods=${root}/mods
+    ///     paths{
+    ///         ${mods}/core
+    ///         $${not-a-reference}
+    ///     }
+    /// ";
+    /// let mut slop: Slop = slop_str.parse().unwrap();
+    /// slop.interpolate(&InterpolateOptions::default()).unwrap();
+    ///
+    /// assert_eq!(slop.get("mods"), Some(&"/opt/game/mods".into()));
+    /// assert_eq!(
+    ///     slop.get("paths"),
+    ///     Some(&vec!["/opt/game/mods/core", "${not-a-reference}"].into()),
+    /// );
+    /// ```
+    pub fn interpolate(&mut self, options: &InterpolateOptions) -> SlopResult<()> {
+        *self = self.interpolated(options)?;
+        Ok(())
+    }
+
+    /// Same as [Slop::interpolate], but returns the expanded KVs in a new
+    /// [Slop] instead.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{InterpolateOptions, Slop, error::SlopError};
+    ///
+    /// let slop: Slop = "a=${b}\nb=${a}".parse().unwrap();
+    /// let err = slop.interpolated(&InterpolateOptions::default()).unwrap_err();
+    ///
+    /// assert!(matches!(err, SlopError::ReferenceCycle { .. }));
+    ///
+    /// let slop: Slop = "a=${missing}".parse().unwrap();
+    /// let err = slop.interpolated(&InterpolateOptions::default()).unwrap_err();
+    ///
+    /// assert_eq!(err.to_string(), "(at `a`) the key `missing` does not exist");
+    /// ```
+    pub fn interpolated(&self, options: &InterpolateOptions) -> SlopResult<Slop> {
+        let mut interpolator = Interpolator {
+            slop: self,
+            options,
+            resolved: BTreeMap::new(),
+            stack: vec![],
+        };
+        let mut slop = Slop::new();
+
+        for (key, value) in self.iter() {
+            let value = match value {
+                SlopValue::String(_) => SlopValue::String(interpolator.resolve(key)?),
+                SlopValue::List(l) => SlopValue::List(l
+                    .iter()
+                    .map(|item| interpolator.expand(key, item))
+                    .collect::<SlopResult<_>>()?),
+            };
+
+            if !value.is_valid() {
+                return Err(SlopError::InvalidValue { value: value.to_string() });
+            }
+
+            slop.insert_unchecked(key.clone(), value);
+        }
+
+        Ok(slop)
+    }
+}
+
+struct Interpolator<'a> {
+    slop: &'a Slop,
+    options: &'a InterpolateOptions,
+    // The expanded values of the string KVs found so far.
+    resolved: BTreeMap<&'a str, String>,
+    // The string KVs being expanded, to detect cycles.
+    stack: Vec<&'a str>,
+}
+
+impl<'a> Interpolator<'a> {
+    // Returns the expanded value of the string KV.
+    fn resolve(&mut self, key: &'a str) -> SlopResult<String> {
+        if let Some(value) = self.resolved.get(key) {
+            return Ok(value.clone());
+        }
+
+        if let Some(start) = self.stack.iter().position(|k| *k == key) {
+            let mut cycle: Vec<_> = self.stack[start..].iter().map(|k| k.to_string()).collect();
+            cycle.push(key.to_string());
+            return Err(SlopError::ReferenceCycle { keys: cycle });
+        }
+
+        // SAFETY: Only the keys of string KVs are resolved.
+        let value = self.slop.get_string(key).unwrap();
+
+        self.stack.push(key);
+        let expanded = self.expand(key, value);
+        self.stack.pop();
+
+        let expanded = expanded?;
+        self.resolved.insert(key, expanded.clone());
+        Ok(expanded)
+    }
+
+    // Expands the references in a value of the KV `key`.
+    fn expand(&mut self, key: &str, value: &'a str) -> SlopResult<String> {
+        let mut expanded = String::with_capacity(value.len());
+        let mut rest = value;
+
+        while let Some(start) = rest.find('$') {
+            expanded.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            if let Some(after) = rest.strip_prefix("$${") {
+                expanded.push_str("${");
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("${") {
+                let Some((name, after)) = after.split_once('}') else {
+                    return Err(invalid(key, format!("`{rest}` is not closed")));
+                };
+
+                expanded.push_str(&self.reference(key, name)?);
+                self.check_len(&expanded)?;
+                rest = after;
+            } else {
+                expanded.push('$');
+                rest = &rest[1..];
+            }
+        }
+
+        expanded.push_str(rest);
+        self.check_len(&expanded)?;
+        Ok(expanded)
+    }
+
+    // Returns an error if the expanded value is longer than the maximum. Every
+    // reference is checked as soon as it's expanded, so that no value grows
+    // much past the maximum.
+    fn check_len(&self, expanded: &str) -> SlopResult<()> {
+        match self.options.max_len {
+            Some(max) if expanded.len() > max => {
+                Err(SlopError::LimitExceeded { limit: Limit::ExpandedLen, line: None, max })
+            },
+            _ => Ok(()),
+        }
+    }
+
+    // Returns the value that `${name}` expands to in the KV `key`.
+    fn reference(&mut self, key: &str, name: &'a str) -> SlopResult<String> {
+        #[cfg(feature = "std")]
+        if self.options.env {
+            if let Some(var) = name.strip_prefix("env:") {
+                return env::var(var).map_err(|e| invalid(key, match e {
+                    env::VarError::NotPresent => format!("the environment variable `{var}` \
+                        is not set"),
+                    env::VarError::NotUnicode(_) => format!("the environment variable \
+                        `{var}` is not valid unicode"),
+                }));
+            }
+        }
+
+        match self.slop.get(name) {
+            Some(SlopValue::String(_)) => self.resolve(name),
+            Some(SlopValue::List(_)) => Err(invalid(key, format!("`{name}` is a list kv, \
+                so it can't be referenced"))),
+            None => Err(invalid(key, format!("the key `{name}` does not exist"))),
+        }
+    }
+}
+
+#[inline]
+fn invalid(key: &str, message: String) -> SlopError {
+    SlopError::InvalidReference { key: key.to_string(), problem: message }
+}
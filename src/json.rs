@@ -0,0 +1,169 @@
+//! Conversions between SLOP and JSON. Requires the `json` feature.
+//!
+//! A [Slop] maps to a JSON object whose values are strings (for string KVs)
+//! or arrays of strings (for list KVs). Any other JSON value is rejected with
+//! [SlopError::UnsupportedJson], since SLOP has no nesting or other types.
+
+use serde_json::Value;
+
+use crate::{
+    Slop,
+    SlopValue,
+    error::{SlopError, SlopResult},
+};
+
+impl Slop {
+    /// Converts the [Slop] into a JSON object. The keys are sorted.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let slop: Slop = "a=1\nb{\nx\ny\n}".parse().unwrap();
+    ///
+    /// assert_eq!(slop.to_json().to_string(), r#"{"a":"1","b":["x","y"]}"#);
+    /// ```
+    pub fn to_json(&self) -> Value {
+        Value::Object(self
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::from(v)))
+            .collect())
+    }
+
+    /// Same as [Slop::to_json], but returns the object as a pretty-printed
+    /// JSON string.
+    #[inline]
+    pub fn to_json_string(&self) -> String {
+        // Serializing a `Value` can't fail.
+        serde_json::to_string_pretty(&self.to_json()).unwrap()
+    }
+
+    /// Converts a JSON object into a new [Slop].
+    ///
+    /// Returns a [SlopError] if the JSON is not an object, if a value is not a
+    /// string or an array of strings, or if a key or value can't be written to
+    /// a SLOP string and read back unchanged.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use slop_rs::Slop;
+    ///
+    /// let slop = Slop::from_json(json!({ "a": "1", "b": ["x", "y"] })).unwrap();
+    ///
+    /// assert_eq!(slop.get("a"), Some(&"1".into()));
+    /// assert_eq!(slop.get("b"), Some(&vec!["x", "y"].into()));
+    ///
+    /// assert!(Slop::from_json(json!({ "a": { "nested": "1" } })).is_err());
+    /// assert!(Slop::from_json(json!({ "a": 1 })).is_err());
+    /// ```
+    ///
+    /// KVs that wouldn't be read back unchanged from a saved file are rejected
+    /// too, with the same rules as [Slop::validate]:
+    ///
+    /// ```
+    /// use serde_json::json;
+    /// use slop_rs::{Slop, error::ErrorKind};
+    ///
+    /// let err = Slop::from_json(json!({ "#hidden": "1" })).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidKey);
+    ///
+    /// let err = Slop::from_json(json!({ " lead": "2" })).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidKey);
+    ///
+    /// let err = Slop::from_json(json!({ "l": ["  x", "y"] })).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    /// ```
+    pub fn from_json(json: Value) -> SlopResult<Self> {
+        let Value::Object(map) = json else {
+            return Err(SlopError::UnsupportedJson {
+                path: String::new(),
+                found: json_type(&json),
+            });
+        };
+
+        let mut slop = Slop::new();
+
+        for (key, value) in map {
+            let value = json_to_value(&key, value)?;
+            slop.insert(key, value)?;
+        }
+
+        Ok(slop)
+    }
+
+    /// Parses a JSON string and converts it into a new [Slop].
+    ///
+    /// See also: [Slop::from_json]
+    #[inline]
+    pub fn from_json_str(json_str: &str) -> SlopResult<Self> {
+        Self::from_json(serde_json::from_str(json_str)?)
+    }
+}
+
+impl From<&SlopValue> for Value {
+    /// Converts a [SlopValue::String] into a JSON string, and a
+    /// [SlopValue::List] into a JSON array of strings.
+    fn from(value: &SlopValue) -> Self {
+        match value {
+            SlopValue::String(s) => Value::String(s.clone()),
+            SlopValue::List(l) => Value::Array(
+                l.iter().cloned().map(Value::String).collect(),
+            ),
+        }
+    }
+}
+
+impl From<SlopValue> for Value {
+    #[inline(always)]
+    fn from(value: SlopValue) -> Self {
+        Value::from(&value)
+    }
+}
+
+impl TryFrom<Value> for SlopValue {
+    type Error = SlopError;
+
+    /// Converts a JSON string or array of strings into a [SlopValue].
+    #[inline(always)]
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        json_to_value("", value)
+    }
+}
+
+// Converts a JSON string or array of strings into a [SlopValue]. `path` is only
+// used for error messages.
+fn json_to_value(path: &str, value: Value) -> SlopResult<SlopValue> {
+    match value {
+        Value::String(s) => Ok(s.into()),
+        Value::Array(a) => a
+            .into_iter()
+            .enumerate()
+            .map(|(i, item)| match item {
+                Value::String(s) => Ok(s),
+                other => Err(SlopError::UnsupportedJson {
+                    path: format!("{path}[{i}]"),
+                    found: json_type(&other),
+                }),
+            })
+            .collect::<SlopResult<Vec<_>>>()
+            .map(SlopValue::List),
+        other => Err(SlopError::UnsupportedJson {
+            path: path.to_string(),
+            found: json_type(&other),
+        }),
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
@@ -20,13 +20,75 @@
 //!
 //! assert_eq!(slop.get("some-string-kv"), Some(&"some value".into()));
 //! ```
+//!
+//! ## `no_std`
+//!
+//! The `std` feature is enabled by default. Without it, the crate only needs
+//! [alloc]: parsing, [Slop], [SlopDocument], formatting, linting and the rest
+//! of the in-memory APIs keep working, but anything that touches files (such
+//! as [Slop::open], includes, atomic saving and the watcher) is left out, and
+//! [Slop] is backed by a [BTreeMap](alloc::collections::BTreeMap) instead of a
+//! [HashMap](std::collections::HashMap). The other features all require
+//! `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "async")]
+pub mod async_io;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "json")]
+pub mod conformance;
+#[cfg(any(feature = "toml", feature = "yaml", feature = "ini"))]
+pub mod convert;
+pub mod diagnostic;
+pub mod document;
+pub mod encoding;
 pub mod error;
+pub mod format;
+#[cfg(feature = "std")]
+pub mod include;
+pub mod interpolate;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod lint;
+pub mod migrate;
+pub mod parse;
+#[cfg(feature = "std")]
+pub mod save;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod section;
 pub mod slop;
+#[cfg(feature = "proptest")]
+pub mod strategy;
 pub mod value;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod watch;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "async")]
+pub use async_io::*;
+pub use diagnostic::*;
+pub use document::*;
+pub use encoding::*;
+pub use format::*;
+#[cfg(feature = "std")]
+pub use include::*;
+pub use interpolate::*;
+pub use lint::*;
+pub use migrate::*;
+pub use parse::*;
+#[cfg(feature = "std")]
+pub use save::*;
+pub use section::*;
 pub use slop::*;
 pub use value::*;
+#[cfg(feature = "std")]
+pub use watch::*;
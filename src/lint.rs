@@ -0,0 +1,328 @@
+//! Defines the SLOP linter, which warns about valid but suspicious constructs.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+
+use alloc::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt::Display;
+
+use crate::{encoding::BOM, slop::clean_up_line};
+
+/// How serious a [Lint] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The string is valid, but probably doesn't mean what the author thinks.
+    Warning,
+
+    /// The string can't be parsed.
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Identifies the kind of a [Lint].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintId {
+    /// A string value contains ` #`, which looks like a trailing comment but
+    /// is part of the value.
+    TrailingComment,
+
+    /// A list item starts with `#`, which looks like a comment but is an item.
+    CommentInList,
+
+    /// A key is defined more than once. Only the last KV is kept.
+    DuplicateKey,
+
+    /// A key ends in whitespace, which is part of the key.
+    KeyWhitespace,
+
+    /// A list KV was written on one line, as in `key{}`. This is invalid.
+    InlineList,
+
+    /// A value ends in `\r`, which is removed when the string is parsed again.
+    NoRoundTrip,
+
+    /// The line is not a valid string KV or list KV starter.
+    InvalidLine,
+
+    /// A list KV is never closed.
+    UnclosedList,
+
+    /// A string KV's or list KV's key ends in `{`.
+    InvalidKey,
+}
+
+impl LintId {
+    /// Returns the ID as it is shown to users, such as `trailing-comment`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TrailingComment => "trailing-comment",
+            Self::CommentInList => "comment-in-list",
+            Self::DuplicateKey => "duplicate-key",
+            Self::KeyWhitespace => "key-whitespace",
+            Self::InlineList => "inline-list",
+            Self::NoRoundTrip => "no-round-trip",
+            Self::InvalidLine => "invalid-line",
+            Self::UnclosedList => "unclosed-list",
+            Self::InvalidKey => "invalid-key",
+        }
+    }
+
+    /// Returns the [Severity] of the lints with this ID.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::InlineList
+                | Self::InvalidLine
+                | Self::UnclosedList
+                | Self::InvalidKey => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+impl Display for LintId {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A problem found by [lint].
+///
+/// Lines and columns are 0-based, and columns count characters. Both are
+/// written as 1-based when displayed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// The kind of problem.
+    pub id: LintId,
+
+    /// The 0-based index of the line.
+    pub line: usize,
+
+    /// The 0-based index of the character the problem starts at.
+    pub column: usize,
+
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Lint {
+    /// Returns the [Severity] of the lint's [LintId].
+    #[inline(always)]
+    pub fn severity(&self) -> Severity {
+        self.id.severity()
+    }
+}
+
+impl Display for Lint {
+    /// Displays the lint as `(in line L, column C) severity[id]: message`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "(in line {}, column {}) {}[{}]: {}",
+            self.line + 1,
+            self.column + 1,
+            self.severity(),
+            self.id,
+            self.message,
+        )
+    }
+}
+
+/// Checks a SLOP string for suspicious constructs.
+///
+/// Unlike the parser, the linter doesn't stop at the first error, so the
+/// results may also contain [Severity::Error] lints for the lines that can't
+/// be parsed. Lints are sorted by position. A BOM at the start of the string
+/// is skipped, and isn't counted in the columns.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{LintId, lint};
+///
+/// let slop_str = "\
+///     color=red # the best one
+///     list{
+///         #item
+///     }
+///     color=blue
+///     empty{}
+/// ";
+/// let ids: Vec<LintId> = lint(slop_str).into_iter().map(|l| l.id).collect();
+///
+/// assert_eq!(ids, vec![
+///     LintId::TrailingComment,
+///     LintId::CommentInList,
+///     LintId::DuplicateKey,
+///     LintId::InlineList,
+/// ]);
+///
+/// // The parser rejects a list KV whose key ends in `{`.
+/// let lints = lint("list{{\n    a\n}");
+/// assert_eq!(lints[0].id, LintId::InvalidKey);
+/// assert_eq!(lints[0].message, "the key `list{` ends in `{`");
+/// ```
+pub fn lint(slop_str: &str) -> Vec<Lint> {
+    // Like the parser, skip the BOM, which editors don't show either.
+    let slop_str = slop_str.strip_prefix(BOM).unwrap_or(slop_str);
+    let lines: Vec<&str> = slop_str.split('\n').collect();
+    let mut lints = vec![];
+    let mut keys: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = clean_up_line(lines[i]);
+        let indent = char_count(lines[i]) - char_count(lines[i].trim_start());
+        let start = i;
+        i += 1;
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let key = if let Some((key, value)) = line.split_once('=') {
+            if key.ends_with('{') {
+                lints.push(lint_at(LintId::InvalidKey, start, indent, format!(
+                    "the key `{key}` ends in `{{`",
+                )));
+            }
+
+            let value_column = indent + char_count(key) + 1;
+
+            if let Some(pos) = find_trailing_comment(value) {
+                lints.push(lint_at(
+                    LintId::TrailingComment,
+                    start,
+                    value_column + char_count(&value[..pos]),
+                    "`#` is part of the value, not the start of a comment"
+                        .to_string(),
+                ));
+            }
+
+            if value.ends_with('\r') {
+                lints.push(lint_at(
+                    LintId::NoRoundTrip,
+                    start,
+                    value_column + char_count(value) - 1,
+                    "the value ends in `\\r`, which will be lost when saved"
+                        .to_string(),
+                ));
+            }
+
+            key
+        } else if let Some(key) = line.strip_suffix('{') {
+            let mut closed = false;
+
+            if key.ends_with('{') {
+                lints.push(lint_at(LintId::InvalidKey, start, indent, format!(
+                    "the key `{key}` ends in `{{`",
+                )));
+            }
+
+            while i < lines.len() {
+                let item = clean_up_line(lines[i]);
+                let item_column = char_count(lines[i]) - char_count(lines[i].trim_start());
+                i += 1;
+
+                if item == "}" {
+                    closed = true;
+                    break;
+                }
+
+                if item.starts_with('#') {
+                    lints.push(lint_at(
+                        LintId::CommentInList,
+                        i - 1,
+                        item_column,
+                        "lines inside of lists are items, even if they start \
+                            with `#`".to_string(),
+                    ));
+                }
+
+                if item.ends_with('\r') {
+                    lints.push(lint_at(
+                        LintId::NoRoundTrip,
+                        i - 1,
+                        item_column + char_count(item) - 1,
+                        "the item ends in `\\r`, which will be lost when saved"
+                            .to_string(),
+                    ));
+                }
+            }
+
+            if !closed {
+                lints.push(lint_at(LintId::UnclosedList, start, indent, format!(
+                    "`{line}` is not closed",
+                )));
+            }
+
+            key
+        } else if line.trim_end().ends_with("{}") {
+            lints.push(lint_at(
+                LintId::InlineList,
+                start,
+                indent,
+                "empty list KVs must be written as `key{` followed by `}` \
+                    on its own line".to_string(),
+            ));
+            continue;
+        } else {
+            lints.push(lint_at(LintId::InvalidLine, start, indent, format!(
+                "`{line}` is not a valid kv",
+            )));
+            continue;
+        };
+
+        if key.len() != key.trim_end().len() {
+            lints.push(lint_at(
+                LintId::KeyWhitespace,
+                start,
+                indent + char_count(key.trim_end()),
+                format!("the key `{key}` ends in whitespace, which is part of the key"),
+            ));
+        }
+
+        if let Some(prev) = keys.insert(key, start) {
+            lints.push(lint_at(LintId::DuplicateKey, start, indent, format!(
+                "the key `{key}` was already defined in line {}, \
+                    and will be overridden",
+                prev + 1,
+            )));
+        }
+    }
+
+    lints.sort_by_key(|l| (l.line, l.column));
+    lints
+}
+
+#[inline]
+fn lint_at(id: LintId, line: usize, column: usize, message: String) -> Lint {
+    Lint { id, line, column, message }
+}
+
+#[inline]
+fn char_count(s: &str) -> usize {
+    s.chars().count()
+}
+
+// Returns the byte index of a `#` that follows whitespace, if any.
+fn find_trailing_comment(value: &str) -> Option<usize> {
+    value
+        .char_indices()
+        .zip(value.chars().skip(1))
+        .find(|((_, c), next)| c.is_whitespace() && *next == '#')
+        .map(|((i, c), _)| i + c.len_utf8())
+}
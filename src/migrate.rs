@@ -0,0 +1,358 @@
+//! Defines the [Migrator], which upgrades [Slop]s saved by older versions of a
+//! program to the current format version.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! The format version of a [Slop] is stored in the reserved
+//! [`format-version`](Slop::VERSION_KEY) string KV, as a whole number, so it
+//! is read by [Slop::open] and the other parsers like any other KV, and saved
+//! along with the rest of the file. [Slop::version] and [Slop::set_version]
+//! read and write it. A [Slop] without the KV is at version `0`, which is
+//! where files saved before a program adopted versions end up.
+//!
+//! A [Migrator] holds the current version of the format, and the steps that
+//! upgrade a [Slop] from each older version to the next one. Steps can rename,
+//! split or remove KVs, or run any function on the [Slop].
+//!
+//! ## Examples
+//!
+//! ```
+//! use slop_rs::{Migrator, Slop};
+//!
+//! let mut migrator = Migrator::new(2);
+//! migrator.rename(0, "hp", "health");
+//! migrator.split(1, "items", ",");
+//! migrator.remove(1, "legacy-flag");
+//!
+//! let mut slop: Slop = "hp=10\nitems=sword, shield\nlegacy-flag=1".parse().unwrap();
+//! let applied = migrator.migrate(&mut slop).unwrap();
+//!
+//! assert_eq!(slop.get("health"), Some(&"10".into()));
+//! assert_eq!(slop.get("items"), Some(&vec!["sword", "shield"].into()));
+//! assert!(!slop.contains_key("legacy-flag"));
+//! assert_eq!(slop.version().unwrap(), Some(2));
+//!
+//! let applied: Vec<String> = applied.iter().map(|step| step.to_string()).collect();
+//! assert_eq!(applied, [
+//!     "0 -> 1: rename `hp` to `health`",
+//!     "1 -> 2: split `items` on `,`",
+//!     "1 -> 2: remove `legacy-flag`",
+//! ]);
+//! ```
+
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::fmt;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use crate::{
+    Slop,
+    SlopValue,
+    error::{SlopError, SlopResult},
+    slop::is_exact_item,
+};
+
+type StepFn = Box<dyn Fn(&mut Slop) -> SlopResult<()> + Send + Sync>;
+
+/// Upgrades [Slop]s from older format versions to the current one, one
+/// version at a time. (See [migrate](crate::migrate))
+///
+/// Steps are registered with the version they upgrade from, and run in the
+/// order they were registered. A version can have any number of steps,
+/// including none, in which case upgrading from it only changes the version.
+pub struct Migrator {
+    current: u32,
+    steps: Vec<Step>,
+}
+
+struct Step {
+    from: u32,
+    description: String,
+    run: StepFn,
+}
+
+/// A step applied by [Migrator::migrate].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AppliedStep {
+    /// The version the step upgraded from.
+    pub from: u32,
+
+    /// The version the step upgraded to, which is always `from + 1`.
+    pub to: u32,
+
+    /// The description of the step, such as "rename `hp` to `health`".
+    pub description: String,
+}
+
+impl fmt::Display for AppliedStep {
+    /// Displays the step as `from -> to: description`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}: {}", self.from, self.to, self.description)
+    }
+}
+
+impl Migrator {
+    /// Constructs a [Migrator] without steps, that upgrades to the `current`
+    /// version.
+    #[inline(always)]
+    pub fn new(current: u32) -> Self {
+        Self { current, steps: vec![] }
+    }
+
+    /// Returns the version that [Migrator::migrate] upgrades to.
+    #[inline(always)]
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+
+    /// Registers a step that upgrades from version `from` to `from + 1` by
+    /// calling `run`. `description` is returned in the [AppliedStep].
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `from` is not older than the current version.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{Migrator, Slop};
+    ///
+    /// let mut migrator = Migrator::new(1);
+    /// migrator.step(0, "double the volume", |slop| {
+    ///     let volume: u32 = slop.get_string("volume").map_or(Ok(5), |v| v.parse()).unwrap();
+    ///     slop.insert("volume".to_string(), (volume * 2).to_string())?;
+    ///     Ok(())
+    /// });
+    ///
+    /// let mut slop: Slop = "volume=3".parse().unwrap();
+    /// migrator.migrate(&mut slop).unwrap();
+    ///
+    /// assert_eq!(slop.get("volume"), Some(&"6".into()));
+    /// ```
+    pub fn step<F>(&mut self, from: u32, description: impl Into<String>, run: F)
+    where
+        F: Fn(&mut Slop) -> SlopResult<()> + Send + Sync + 'static,
+    {
+        assert!(
+            from < self.current,
+            "the step from version {from} is not older than the current version {}",
+            self.current,
+        );
+
+        self.steps.push(Step { from, description: description.into(), run: Box::new(run) });
+    }
+
+    /// Registers a step that renames the KV `old` to `new`, replacing any KV
+    /// that is already called `new`. Does nothing if there is no `old` KV.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `from` is not older than the current version.
+    pub fn rename(&mut self, from: u32, old: impl Into<String>, new: impl Into<String>) {
+        let (old, new) = (old.into(), new.into());
+        let description = format!("rename `{old}` to `{new}`");
+
+        self.step(from, description, move |slop| {
+            if let Some(value) = slop.remove(&old) {
+                slop.insert(new.clone(), value)?;
+            }
+
+            Ok(())
+        });
+    }
+
+    /// Registers a step that turns the string KV `key` into a list KV, by
+    /// splitting it on `separator` and trimming the whitespace around every
+    /// item. An empty string becomes an empty list. Does nothing if there is
+    /// no `key` KV, or if it's already a list KV.
+    ///
+    /// The step fails with [SlopError::InvalidValue] if an item can't be
+    /// written to a list, such as `}`, and leaves the KV as it is.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `from` is not older than the current version.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{Migrator, Slop, error::ErrorKind};
+    ///
+    /// let mut migrator = Migrator::new(1);
+    /// migrator.split(0, "k", ",");
+    ///
+    /// let mut slop: Slop = "k=a, }, b".parse().unwrap();
+    /// let err = migrator.migrate(&mut slop).unwrap_err();
+    ///
+    /// assert_eq!(err.kind(), ErrorKind::InvalidValue);
+    /// assert_eq!(slop.get("k"), Some(&"a, }, b".into()));
+    /// assert_eq!(slop.version().unwrap(), None);
+    /// ```
+    pub fn split(&mut self, from: u32, key: impl Into<String>, separator: impl Into<String>) {
+        let (key, separator) = (key.into(), separator.into());
+        let description = format!("split `{key}` on `{separator}`");
+
+        self.step(from, description, move |slop| {
+            if let Some(s) = slop.get_string(&key) {
+                let items: Vec<String> = if s.trim().is_empty() {
+                    vec![]
+                } else {
+                    s.split(separator.as_str()).map(|i| i.trim().to_string()).collect()
+                };
+
+                if let Some(item) = items.iter().find(|i| !is_exact_item(i)) {
+                    return Err(SlopError::InvalidValue { value: item.clone() });
+                }
+
+                slop.insert_unchecked(key.clone(), items);
+            }
+
+            Ok(())
+        });
+    }
+
+    /// Registers a step that removes the KV `key`, if it exists.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `from` is not older than the current version.
+    pub fn remove(&mut self, from: u32, key: impl Into<String>) {
+        let key = key.into();
+        let description = format!("remove `{key}`");
+
+        self.step(from, description, move |slop| {
+            slop.remove(&key);
+            Ok(())
+        });
+    }
+
+    /// Upgrades the [Slop] to the current version, running the steps of every
+    /// version from the [Slop]'s one up, and returns the steps that were
+    /// applied, in order.
+    ///
+    /// The version of the [Slop] is updated after the steps of each version,
+    /// so if a step fails, the [Slop] is left at the last version it fully
+    /// reached, with the changes of the failed version's earlier steps
+    /// applied.
+    ///
+    /// Returns [SlopError::InvalidVersion] if the version of the [Slop] is not
+    /// a number, and [SlopError::UnsupportedVersion] if it's newer than the
+    /// current version.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{Migrator, Slop, error::ErrorKind};
+    ///
+    /// let migrator = Migrator::new(1);
+    ///
+    /// // A Slop without a version is at version 0, and gets the current one.
+    /// let mut slop = Slop::new();
+    /// assert!(migrator.migrate(&mut slop).unwrap().is_empty());
+    /// assert_eq!(slop.version().unwrap(), Some(1));
+    ///
+    /// let mut slop: Slop = "format-version=2".parse().unwrap();
+    /// let err = migrator.migrate(&mut slop).unwrap_err();
+    /// assert_eq!(err.kind(), ErrorKind::UnsupportedVersion);
+    /// assert_eq!(err.to_string(), "the format version 2 is newer than the current version 1");
+    /// ```
+    pub fn migrate(&self, slop: &mut Slop) -> SlopResult<Vec<AppliedStep>> {
+        let version = slop.version()?.unwrap_or(0);
+
+        if version > self.current {
+            return Err(SlopError::UnsupportedVersion { version, current: self.current });
+        }
+
+        let mut applied = vec![];
+
+        for from in version..self.current {
+            for step in self.steps.iter().filter(|s| s.from == from) {
+                (step.run)(slop)?;
+                applied.push(AppliedStep {
+                    from,
+                    to: from + 1,
+                    description: step.description.clone(),
+                });
+            }
+
+            slop.set_version(from + 1);
+        }
+
+        Ok(applied)
+    }
+
+    /// Opens the file at the provided path with [Slop::open] and upgrades it
+    /// with [Migrator::migrate]. The file itself is left as it is.
+    ///
+    /// Returns the upgraded [Slop] and the steps that were applied.
+    #[cfg(feature = "std")]
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> SlopResult<(Slop, Vec<AppliedStep>)> {
+        let mut slop = Slop::open(&path)?;
+        let applied = self.migrate(&mut slop).map_err(|e| e.with_path(&path))?;
+
+        Ok((slop, applied))
+    }
+}
+
+impl fmt::Debug for Migrator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<_> = self
+            .steps
+            .iter()
+            .map(|s| format!("{} -> {}: {}", s.from, s.from + 1, s.description))
+            .collect();
+
+        f.debug_struct("Migrator")
+            .field("current", &self.current)
+            .field("steps", &steps)
+            .finish()
+    }
+}
+
+impl Slop {
+    /// The key of the string KV that holds the format version of the [Slop].
+    /// (See [migrate](crate::migrate))
+    pub const VERSION_KEY: &'static str = "format-version";
+
+    /// Returns the format version of the [Slop], or [None] if it doesn't have
+    /// one.
+    ///
+    /// Returns [SlopError::InvalidVersion] if the version is not a whole
+    /// number, or is a list KV.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let slop: Slop = "format-version=3\nname=main".parse().unwrap();
+    /// assert_eq!(slop.version().unwrap(), Some(3));
+    ///
+    /// assert_eq!(Slop::new().version().unwrap(), None);
+    /// assert!("format-version=three".parse::<Slop>().unwrap().version().is_err());
+    /// ```
+    pub fn version(&self) -> SlopResult<Option<u32>> {
+        match self.get(Self::VERSION_KEY) {
+            None => Ok(None),
+            Some(SlopValue::String(s)) => {
+                s.parse().map(Some).map_err(|_| SlopError::InvalidVersion { version: s.clone() })
+            },
+            Some(value @ SlopValue::List(_)) => {
+                Err(SlopError::InvalidVersion { version: value.to_string() })
+            },
+        }
+    }
+
+    /// Sets the format version of the [Slop].
+    #[inline(always)]
+    pub fn set_version(&mut self, version: u32) {
+        self.insert_unchecked(Self::VERSION_KEY.to_string(), version.to_string());
+    }
+}
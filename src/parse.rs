@@ -0,0 +1,329 @@
+//! Defines [ParseOptions], for parsing SLOP from sources that can't be
+//! trusted, such as files uploaded by users.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! [Slop::parse_with] and [Slop::read_with] parse the input one line at a
+//! time, and stop with [SlopError::LimitExceeded] as soon as a limit is
+//! exceeded. [Slop::read_with] never reads more than
+//! [ParseOptions::max_bytes] (plus one) bytes from the reader, so that is the
+//! limit that bounds the memory used, and the one to set for untrusted input.
+//!
+//! Unlike [Slop::open], these functions don't follow `#!include` directives,
+//! which would let the input read other files.
+//!
+//! [Slop::read_with] decodes the input like [decode](crate::decode). UTF-8 is
+//! decoded one line at a time, but UTF-16 is read whole (up to the limit)
+//! before it's parsed.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+#[cfg(feature = "std")]
+use crate::encoding::{Encoding, decode, decode_line};
+use crate::{
+    Slop,
+    SlopValue,
+    encoding::BOM,
+    error::{SlopError, SlopResult},
+    slop::{clean_up_line, in_line, is_valid_key, strip_cr},
+};
+
+/// Options for [Slop::parse_with] and [Slop::read_with], mostly limits. (See
+/// [parse](crate::parse))
+///
+/// Every limit is [None] (unlimited) by default. Lengths are in bytes.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{ParseOptions, Slop, error::ErrorKind};
+///
+/// let options = ParseOptions { max_list_items: Some(2), ..Default::default() };
+/// let err = Slop::parse_with("list{\n    a\n    b\n    c\n}", &options).unwrap_err();
+///
+/// assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+/// assert_eq!(err.line(), Some(3));
+/// assert_eq!(err.to_string(), "(in line 4) the list has more than 2 items");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// The maximum size of the whole input, line breaks included.
+    pub max_bytes: Option<usize>,
+
+    /// The maximum length of a key.
+    pub max_key_len: Option<usize>,
+
+    /// The maximum length of the value of a string KV, and of every item of a
+    /// list KV.
+    pub max_value_len: Option<usize>,
+
+    /// The maximum number of KVs. A KV that replaces another one with the same
+    /// key is not counted again.
+    pub max_kvs: Option<usize>,
+
+    /// The maximum number of items in a list KV.
+    pub max_list_items: Option<usize>,
+
+    /// If `true`, [Slop::read_with] replaces bytes that aren't valid in the
+    /// encoding of the input with `U+FFFD`, instead of returning
+    /// [SlopError::InvalidEncoding].
+    ///
+    /// Defaults to `false`.
+    pub lossy: bool,
+}
+
+/// A limit of [ParseOptions] or [InterpolateOptions](crate::InterpolateOptions),
+/// returned in [SlopError::LimitExceeded].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Limit {
+    /// See [ParseOptions::max_bytes].
+    Bytes,
+    /// See [ParseOptions::max_key_len].
+    KeyLen,
+    /// See [ParseOptions::max_value_len].
+    ValueLen,
+    /// See [ParseOptions::max_kvs].
+    Kvs,
+    /// See [ParseOptions::max_list_items].
+    ListItems,
+    /// See [InterpolateOptions::max_len](crate::InterpolateOptions::max_len).
+    ExpandedLen,
+}
+
+impl Slop {
+    /// Parses a SLOP string, enforcing the limits of the [ParseOptions].
+    /// `#!include` directives are treated as comments.
+    ///
+    /// Returns [SlopError::LimitExceeded] as soon as a limit is exceeded.
+    pub fn parse_with(slop_str: &str, options: &ParseOptions) -> SlopResult<Self> {
+        let mut parser = Parser::new(options.clone());
+        let mut lines = slop_str.split('\n').enumerate().peekable();
+
+        while let Some((index, line)) = lines.next() {
+            let newline = usize::from(lines.peek().is_some());
+            parser.read(index, line.len() + newline)?;
+
+            let line = if index == 0 { line.strip_prefix(BOM).unwrap_or(line) } else { line };
+            parser.line(index, line)?;
+        }
+
+        parser.finish()
+    }
+
+    /// Reads and parses a SLOP string from the reader, one line at a time,
+    /// enforcing the limits of the [ParseOptions]. `#!include` directives are
+    /// treated as comments.
+    ///
+    /// Returns [SlopError::LimitExceeded] as soon as a limit is exceeded,
+    /// without reading the rest of the input.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{ParseOptions, Slop};
+    ///
+    /// let options = ParseOptions { max_bytes: Some(1024), ..Default::default() };
+    /// let huge = "a=1\n".repeat(1_000_000);
+    /// let err = Slop::read_with(huge.as_bytes(), &options).unwrap_err();
+    ///
+    /// assert_eq!(err.to_string(), "(in line 257) the input is longer than 1024 bytes");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn read_with<R: BufRead>(reader: R, options: &ParseOptions) -> SlopResult<Self> {
+        // One byte more than the limit is enough to know that it's exceeded.
+        let limit = options.max_bytes.map_or(u64::MAX, |max| max as u64 + 1);
+        let mut reader = reader.take(limit);
+
+        if Encoding::detect(reader.fill_buf()?) != Encoding::Utf8 {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            return parse_utf16(&bytes, options);
+        }
+
+        let mut parser = Parser::new(options.clone());
+        let mut buf = Vec::new();
+        let mut offset = 0;
+
+        // Like `str::split`, an input that ends in a line break has an empty
+        // last line.
+        for index in 0.. {
+            buf.clear();
+            let bytes = reader.read_until(b'\n', &mut buf)?;
+            parser.read(index, bytes)?;
+
+            let ends = buf.last() == Some(&b'\n');
+            let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+            let line = decode_line(line, index, offset, options.lossy)?;
+            parser.line(index, &line)?;
+            offset += bytes;
+
+            if !ends {
+                break;
+            }
+        }
+
+        parser.finish()
+    }
+
+    /// Opens a file and parses it with [Slop::read_with].
+    #[cfg(feature = "std")]
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: &ParseOptions)
+        -> SlopResult<Self>
+    {
+        let named = |e: SlopError| e.with_path(&path);
+        let file = File::open(&path).map_err(|e| named(e.into()))?;
+
+        Self::read_with(BufReader::new(file), options).map_err(named)
+    }
+}
+
+// Parses UTF-16 that was read whole by `Slop::read_with`.
+#[cfg(feature = "std")]
+fn parse_utf16(bytes: &[u8], options: &ParseOptions) -> SlopResult<Slop> {
+    if let Some(max) = options.max_bytes.filter(|max| bytes.len() > *max) {
+        let index = decode(&bytes[..max], true)?.matches('\n').count();
+        return Err(SlopError::LimitExceeded { limit: Limit::Bytes, line: Some(index), max });
+    }
+
+    // The size of the input was just checked, and the decoded string is
+    // usually larger.
+    let options = ParseOptions { max_bytes: None, ..options.clone() };
+    Slop::parse_with(&decode(bytes, options.lossy)?, &options)
+}
+
+// Parses one line at a time, checking the limits as it goes. Also used by
+// `SlopReader`, which takes the KVs from `Parser::kv` instead.
+#[derive(Debug)]
+pub(crate) struct Parser {
+    options: ParseOptions,
+    // The KVs inserted by `Parser::line`, which the `max_kvs` limit counts.
+    slop: Slop,
+    bytes: usize,
+    // The list KV being parsed.
+    list: Option<List>,
+}
+
+#[derive(Debug)]
+struct List {
+    key: String,
+    items: Vec<String>,
+    start_index: usize,
+    start: String,
+}
+
+impl Parser {
+    pub(crate) fn new(options: ParseOptions) -> Self {
+        Self { options, slop: Slop::new(), bytes: 0, list: None }
+    }
+
+    // Counts the size of the line in the input, with its `\n`.
+    fn read(&mut self, index: usize, bytes: usize) -> SlopResult<()> {
+        self.bytes += bytes;
+        check(Limit::Bytes, self.options.max_bytes, self.bytes, index)
+    }
+
+    // Parses the line, without its `\n`, and inserts the KV it ends, if any.
+    fn line(&mut self, index: usize, line: &str) -> SlopResult<()> {
+        if let Some((key, value)) = self.kv(index, line)? {
+            self.slop.insert_unchecked(key, value);
+        }
+
+        Ok(())
+    }
+
+    // Parses the line, without its `\n`, and returns the KV it ends, if any.
+    pub(crate) fn kv(&mut self, index: usize, line: &str)
+        -> SlopResult<Option<(String, SlopValue)>>
+    {
+        let cleaned = clean_up_line(line);
+
+        if let Some(list) = &mut self.list {
+            if cleaned == "}" {
+                // SAFETY: `self.list` was just matched.
+                let list = self.list.take().unwrap();
+                let key = valid_key(list.key).map_err(in_line(list.start_index, &list.start))?;
+                return Ok(Some((key, list.items.into())));
+            }
+
+            check(Limit::ListItems, self.options.max_list_items, list.items.len() + 1, index)?;
+            check(Limit::ValueLen, self.options.max_value_len, cleaned.len(), index)?;
+            list.items.push(cleaned.to_string());
+            return Ok(None);
+        }
+
+        if cleaned.is_empty() || cleaned.starts_with('#') {
+            return Ok(None);
+        }
+
+        if let Some((key, value)) = cleaned.split_once('=') {
+            self.check_key(key, index)?;
+            check(Limit::ValueLen, self.options.max_value_len, value.len(), index)?;
+            let key = valid_key(key.to_string()).map_err(in_line(index, line))?;
+            Ok(Some((key, value.into())))
+        } else if let Some(key) = cleaned.strip_suffix('{') {
+            self.check_key(key, index)?;
+            self.list = Some(List {
+                key: key.to_string(),
+                items: Vec::new(),
+                start_index: index,
+                start: strip_cr(line).to_string(),
+            });
+            Ok(None)
+        } else {
+            Err(SlopError::InvalidLine { line: index, text: strip_cr(line).to_string() })
+        }
+    }
+
+    fn check_key(&self, key: &str, index: usize) -> SlopResult<()> {
+        check(Limit::KeyLen, self.options.max_key_len, key.len(), index)?;
+
+        if !self.slop.contains_key(key) {
+            let kvs = self.slop.iter().len() + 1;
+            check(Limit::Kvs, self.options.max_kvs, kvs, index)?;
+        }
+
+        Ok(())
+    }
+
+    // Returns an error if a list KV is still open at the end of the input.
+    pub(crate) fn end(&mut self) -> SlopResult<()> {
+        match self.list.take() {
+            Some(list) => Err(SlopError::UnclosedList { line: list.start_index, text: list.start }),
+            None => Ok(()),
+        }
+    }
+
+    fn finish(mut self) -> SlopResult<Slop> {
+        self.end()?;
+        Ok(self.slop)
+    }
+}
+
+fn valid_key(key: String) -> SlopResult<String> {
+    if is_valid_key(&key) {
+        Ok(key)
+    } else {
+        Err(SlopError::InvalidKey { key })
+    }
+}
+
+// Returns an error if `value` is more than the maximum.
+fn check(limit: Limit, max: Option<usize>, value: usize, index: usize) -> SlopResult<()> {
+    match max {
+        Some(max) if value > max => {
+            Err(SlopError::LimitExceeded { limit, line: Some(index), max })
+        },
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,227 @@
+//! Defines atomic saving, which never leaves a partially written file behind.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! [Slop::save] writes the file in place, so a crash or a full disk partway
+//! through leaves it truncated. [Slop::save_atomic] writes a temporary file
+//! in the same directory instead, flushes it to the disk, and then renames it
+//! over the original, so the file is either fully old or fully new.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{
+    Slop,
+    SlopDocument,
+    error::{SlopError, SlopResult},
+};
+
+/// Options for [Slop::save_atomic] and [SlopDocument::save_atomic].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SaveOptions {
+    /// How many copies of the previous versions of the file to keep.
+    ///
+    /// The most recent copy of `file.slop` is `file.slop.1.bak`, the one
+    /// before it is `file.slop.2.bak`, and so on. The oldest copy is deleted
+    /// when there are too many.
+    ///
+    /// Defaults to `0`.
+    pub backups: usize,
+
+    /// If `true`, list values are indented. (See [Slop::save_pretty])
+    /// [SlopDocument]s are always written as they are.
+    ///
+    /// Defaults to `false`.
+    pub pretty: bool,
+}
+
+impl Slop {
+    /// Same as [Slop::save], but replaces the file atomically. (See
+    /// [save](crate::save))
+    ///
+    /// If the file already exists, its permissions are kept, and the new
+    /// contents are never readable with wider ones. If it is a symbolic link,
+    /// the file it points to is replaced.
+    ///
+    /// Returns a [SlopError::Io](crate::error::SlopError::Io) whose message
+    /// says which step failed. If any step before the rename fails, the
+    /// original file is left untouched, though some backups may have been
+    /// rotated. The last step syncs the directory after the rename, so if
+    /// only that fails, the file has already been replaced, but the
+    /// replacement may not survive a crash.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{SaveOptions, Slop};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("slop-save-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let path = dir.join("config.slop");
+    /// let options = SaveOptions { backups: 2, ..Default::default() };
+    ///
+    /// for i in 1..=3 {
+    ///     let slop: Slop = format!("version={i}").parse().unwrap();
+    ///     slop.save_atomic(&path, &options).unwrap();
+    /// }
+    ///
+    /// let version = |name: &str| Slop::open(dir.join(name)).unwrap().get("version").cloned();
+    ///
+    /// assert_eq!(version("config.slop"), Some("3".into()));
+    /// assert_eq!(version("config.slop.1.bak"), Some("2".into()));
+    /// assert_eq!(version("config.slop.2.bak"), Some("1".into()));
+    ///
+    /// std::fs::remove_dir_all(dir).unwrap();
+    /// ```
+    pub fn save_atomic<P: AsRef<Path>>(&self, path: P, options: &SaveOptions) -> SlopResult<()> {
+        let contents = if options.pretty { self.to_string_pretty() } else { self.to_string() };
+        write_atomic(path.as_ref(), contents.as_bytes(), options)
+            .map_err(|e| SlopError::from(e).with_path(path))
+    }
+}
+
+impl SlopDocument {
+    /// Same as [SlopDocument::save], but replaces the file atomically. (See
+    /// [Slop::save_atomic])
+    pub fn save_atomic<P: AsRef<Path>>(&self, path: P, options: &SaveOptions) -> SlopResult<()> {
+        write_atomic(path.as_ref(), self.to_string().as_bytes(), options)
+            .map_err(|e| SlopError::from(e).with_path(path))
+    }
+}
+
+// Used to give every temporary file made by this process a different name.
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+// Writes the contents to a temporary file next to `path`, then renames it over
+// `path`. Every error says which step failed.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8], options: &SaveOptions)
+    -> io::Result<()>
+{
+    // Renaming over a symbolic link would replace the link itself.
+    let path = match fs::symlink_metadata(path) {
+        Ok(m) if m.file_type().is_symlink() => fs::canonicalize(path).map_err(|e| step(
+            e,
+            format!("couldn't resolve the link `{}`", path.display()),
+        ))?,
+        _ => path.to_path_buf(),
+    };
+    let original = fs::metadata(&path).ok();
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().ok_or_else(|| io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("`{}` is not a file path", path.display()),
+    ))?;
+
+    let temp_path = dir.join(format!(
+        ".{}.{}-{}.tmp",
+        file_name.to_string_lossy(),
+        process::id(),
+        TEMP_COUNTER.fetch_add(1, Ordering::Relaxed),
+    ));
+    let mut open_options = OpenOptions::new();
+    open_options.write(true).create_new(true);
+
+    // The contents must never be readable by more users than the original's,
+    // not even before the permissions are copied.
+    #[cfg(unix)]
+    if let Some(original) = &original {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        open_options.mode(original.permissions().mode() & 0o777);
+    }
+
+    let mut temp = open_options
+        .open(&temp_path)
+        .map_err(|e| step(e, format!("couldn't create the temp file `{}`", temp_path.display())))?;
+
+    let result = (|| {
+        let temp_display = temp_path.display();
+
+        // The umask may have taken some permissions away at creation.
+        if let Some(original) = &original {
+            fs::set_permissions(&temp_path, original.permissions()).map_err(|e| step(
+                e,
+                format!("couldn't copy the permissions of `{}`", path.display()),
+            ))?;
+        }
+
+        temp.write_all(contents)
+            .map_err(|e| step(e, format!("couldn't write the temp file `{temp_display}`")))?;
+        temp.sync_all()
+            .map_err(|e| step(e, format!("couldn't sync the temp file `{temp_display}`")))?;
+
+        if original.is_some() && options.backups > 0 {
+            rotate_backups(&path, options.backups)?;
+        }
+
+        fs::rename(&temp_path, &path).map_err(|e| step(e, format!(
+            "couldn't rename the temp file `{temp_display}` to `{}`",
+            path.display(),
+        )))
+    })();
+
+    if result.is_err() {
+        // The temp file is useless now, and the original error matters more.
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    sync_dir(dir)
+}
+
+// Shifts every backup of `path` one number up, deleting the oldest one, and
+// copies `path` to the first backup.
+fn rotate_backups(path: &Path, backups: usize) -> io::Result<()> {
+    let backup = |n: usize| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{n}.bak"));
+        PathBuf::from(name)
+    };
+
+    for n in (1..backups).rev() {
+        let from = backup(n);
+
+        if from.exists() {
+            fs::rename(&from, backup(n + 1)).map_err(|e| step(e, format!(
+                "couldn't rotate the backup `{}`",
+                from.display(),
+            )))?;
+        }
+    }
+
+    let first = backup(1);
+    fs::copy(path, &first).map_err(|e| step(e, format!(
+        "couldn't back up `{}` to `{}`",
+        path.display(),
+        first.display(),
+    )))?;
+
+    Ok(())
+}
+
+// Makes the rename durable. Directories can only be synced this way on Unix.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)
+        .and_then(|d| d.sync_all())
+        .map_err(|e| step(e, format!("couldn't sync the directory `{}`", dir.display())))
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+// Adds the failed step to the error's message.
+fn step(error: io::Error, step: String) -> io::Error {
+    io::Error::new(error.kind(), format!("{step}: {error}"))
+}
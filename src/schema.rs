@@ -0,0 +1,487 @@
+//! Defines [SlopSchema], which describes the KVs a [Slop] is expected to have.
+//! Requires the `schema` feature.
+//!
+//! Schemas can be built in Rust, or loaded from a SLOP string where each KV is
+//! a property of a key, written as `key.property`. The part before the last
+//! `.` is the key, so dotted keys such as `server.port` work as expected:
+//!
+//! ```slop
+//! # Keys without a `.` are options of the whole schema.
+//! allow-unknown-keys=false
+//!
+//! name.required=true
+//! name.kind=string
+//! name.pattern=^[a-z-]+$
+//! name.description=The name of the mod.
+//!
+//! server.port.min=1
+//! server.port.max=65535
+//!
+//! mode.enum{
+//!     fast
+//!     slow
+//! }
+//!
+//! tags.kind=list
+//! tags.min-items=1
+//! tags.max-items=10
+//! ```
+//!
+//! The available properties match the fields of [KeySchema]: `required`
+//! (`true` or `false`), `kind` (`string` or `list`), `pattern`, `min`, `max`,
+//! `enum`, `min-items`, `max-items` and `description`.
+
+use std::{
+    collections::{BTreeMap, btree_map},
+    fmt::Display,
+    path::Path,
+    str::FromStr,
+};
+
+use regex::Regex;
+
+use crate::{
+    Slop,
+    SlopDocument,
+    SlopValue,
+    error::{SlopError, SlopResult},
+};
+
+/// Describes the KVs a [Slop] is expected to have.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{Slop, schema::{KeySchema, SlopSchema, ValueKind, ViolationKind}};
+///
+/// let mut schema = SlopSchema::new();
+/// schema.insert("name".to_string(), KeySchema {
+///     required: true,
+///     kind: Some(ValueKind::String),
+///     ..Default::default()
+/// });
+/// schema.insert("port".to_string(), KeySchema {
+///     min: Some(1.0),
+///     max: Some(65535.0),
+///     ..Default::default()
+/// });
+///
+/// let slop: Slop = "port=80000".parse().unwrap();
+/// let violations = schema.validate(&slop).unwrap_err();
+///
+/// assert_eq!(violations.len(), 2);
+/// assert_eq!(violations[0].kind, ViolationKind::Missing);
+/// assert_eq!(violations[1].key, "port");
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlopSchema {
+    keys: BTreeMap<String, KeySchema>,
+
+    /// Whether KVs that aren't in the schema are allowed. Defaults to `true`.
+    pub allow_unknown_keys: bool,
+}
+
+/// Describes a single KV of a [SlopSchema].
+///
+/// The value checks (`pattern`, `min`, `max` and `allowed`) apply to string
+/// KVs and to each item of list KVs.
+#[derive(Debug, Clone, Default)]
+pub struct KeySchema {
+    /// Whether the KV must be present.
+    pub required: bool,
+
+    /// The kind of KV, or [None] if both are allowed.
+    pub kind: Option<ValueKind>,
+
+    /// A pattern the values must match. Use `^` and `$` to match the whole
+    /// value.
+    pub pattern: Option<Regex>,
+
+    /// The smallest number the values can be. Non-numeric values, `NaN` and
+    /// infinities fail this check.
+    pub min: Option<f64>,
+
+    /// The largest number the values can be. Non-numeric values, `NaN` and
+    /// infinities fail this check.
+    pub max: Option<f64>,
+
+    /// The only values that are allowed.
+    pub allowed: Option<Vec<String>>,
+
+    /// The smallest number of items a list KV can have.
+    pub min_items: Option<usize>,
+
+    /// The largest number of items a list KV can have.
+    pub max_items: Option<usize>,
+
+    /// A description of the KV, for tools such as editors.
+    pub description: Option<String>,
+}
+
+/// The kinds of KV.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueKind {
+    /// A string KV.
+    String,
+
+    /// A list KV.
+    List,
+}
+
+impl ValueKind {
+    /// Returns the kind of the value.
+    pub fn of(value: &SlopValue) -> Self {
+        match value {
+            SlopValue::String(_) => Self::String,
+            SlopValue::List(_) => Self::List,
+        }
+    }
+}
+
+impl Display for ValueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::String => "string",
+            Self::List => "list",
+        })
+    }
+}
+
+/// A KV that doesn't follow its [SlopSchema].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The key of the KV.
+    pub key: String,
+
+    /// The 0-based index of the line the problem is in, if it is known.
+    /// Written as 1-based when displayed.
+    pub line: Option<usize>,
+
+    /// What is wrong with the KV.
+    pub kind: ViolationKind,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "(in line {}) ", line + 1)?;
+        }
+        write!(f, "`{}`: {}", self.key, self.kind)
+    }
+}
+
+/// The ways a KV can fail to follow its [SlopSchema].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    /// The KV is required, but missing.
+    Missing,
+
+    /// The KV is not in the schema, and unknown keys aren't allowed.
+    Unknown,
+
+    /// The KV is of the wrong kind.
+    WrongKind(ValueKind),
+
+    /// The value doesn't match [KeySchema::pattern].
+    PatternMismatch(String),
+
+    /// The value is not a finite number, but [KeySchema::min] or
+    /// [KeySchema::max] is set.
+    NotANumber(String),
+
+    /// The value is smaller than [KeySchema::min] or larger than
+    /// [KeySchema::max].
+    OutOfRange(String),
+
+    /// The value is not one of [KeySchema::allowed].
+    NotAllowed(String),
+
+    /// The list KV has fewer items than [KeySchema::min_items].
+    TooFewItems(usize),
+
+    /// The list KV has more items than [KeySchema::max_items].
+    TooManyItems(usize),
+}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "the key is required, but missing"),
+            Self::Unknown => write!(f, "the key is not in the schema"),
+            Self::WrongKind(k) => write!(f, "expected a {k} kv"),
+            Self::PatternMismatch(v) => write!(f, "`{v}` doesn't match the pattern"),
+            Self::NotANumber(v) => write!(f, "`{v}` is not a number"),
+            Self::OutOfRange(v) => write!(f, "`{v}` is out of range"),
+            Self::NotAllowed(v) => write!(f, "`{v}` is not one of the allowed values"),
+            Self::TooFewItems(n) => write!(f, "the list has too few items ({n})"),
+            Self::TooManyItems(n) => write!(f, "the list has too many items ({n})"),
+        }
+    }
+}
+
+impl SlopSchema {
+    /// Constructs an empty [SlopSchema], which allows unknown keys.
+    pub fn new() -> Self {
+        Self { keys: BTreeMap::new(), allow_unknown_keys: true }
+    }
+
+    /// Reads the contents of a file and loads it as a schema.
+    ///
+    /// See the [module documentation](self) for the format.
+    #[inline(always)]
+    pub fn open<P: AsRef<Path>>(path: P) -> SlopResult<Self> {
+        let slop = Slop::open(&path)?;
+        Self::from_slop(&slop).map_err(|e| e.with_path(path))
+    }
+
+    /// Loads a schema from a [Slop].
+    ///
+    /// See the [module documentation](self) for the format.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{Slop, schema::SlopSchema};
+    ///
+    /// let schema: SlopSchema = "\
+    ///     tags.kind=list
+    ///     tags.max-items=2
+    /// ".parse().unwrap();
+    ///
+    /// let slop: Slop = "tags{\na\nb\nc\n}".parse().unwrap();
+    /// assert!(schema.validate(&slop).is_err());
+    /// ```
+    pub fn from_slop(slop: &Slop) -> SlopResult<Self> {
+        let mut schema = Self::new();
+        let mut kvs: Vec<_> = slop.iter().collect();
+        kvs.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (full_key, value) in kvs {
+            let invalid = |reason: String| SlopError::InvalidSchema {
+                key: full_key.clone(),
+                problem: reason,
+            };
+            let string = || value
+                .string()
+                .ok_or_else(|| invalid("expected a string kv".to_string()));
+            let number = || string()?
+                .parse::<f64>()
+                .ok()
+                .filter(|n| n.is_finite())
+                .ok_or_else(|| invalid("expected a finite number".to_string()));
+            let count = || string()?
+                .parse::<usize>()
+                .map_err(|_| invalid("expected a positive integer".to_string()));
+            let boolean = || match string()?.as_str() {
+                "true" => Ok(true),
+                "false" => Ok(false),
+                _ => Err(invalid("expected `true` or `false`".to_string())),
+            };
+
+            let Some((key, property)) = full_key.rsplit_once('.') else {
+                match full_key.as_str() {
+                    "allow-unknown-keys" => schema.allow_unknown_keys = boolean()?,
+                    _ => return Err(invalid("unknown schema option".to_string())),
+                }
+                continue;
+            };
+
+            let key_schema = schema.keys.entry(key.to_string()).or_default();
+
+            match property {
+                "required" => key_schema.required = boolean()?,
+                "kind" => key_schema.kind = Some(match string()?.as_str() {
+                    "string" => ValueKind::String,
+                    "list" => ValueKind::List,
+                    _ => return Err(invalid("expected `string` or `list`".to_string())),
+                }),
+                "pattern" => key_schema.pattern = Some(
+                    Regex::new(string()?).map_err(|e| invalid(e.to_string()))?,
+                ),
+                "min" => key_schema.min = Some(number()?),
+                "max" => key_schema.max = Some(number()?),
+                "enum" => key_schema.allowed = Some(value
+                    .list()
+                    .ok_or_else(|| invalid("expected a list kv".to_string()))?
+                    .clone()),
+                "min-items" => key_schema.min_items = Some(count()?),
+                "max-items" => key_schema.max_items = Some(count()?),
+                "description" => key_schema.description = Some(string()?.clone()),
+                _ => return Err(invalid(format!("unknown property `{property}`"))),
+            }
+        }
+
+        Ok(schema)
+    }
+
+    /// Adds the schema of a KV, returning the previous one, if any.
+    pub fn insert(&mut self, key: String, key_schema: KeySchema) -> Option<KeySchema> {
+        self.keys.insert(key, key_schema)
+    }
+
+    /// Returns the schema of the KV defined by `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&KeySchema> {
+        self.keys.get(key)
+    }
+
+    /// Iterates over the schemas of the KVs, sorted by key.
+    pub fn iter(&self) -> btree_map::Iter<'_, String, KeySchema> {
+        self.keys.iter()
+    }
+
+    /// Checks the [Slop] against the schema, returning every [Violation] if
+    /// there are any. Violations are sorted by key.
+    ///
+    /// Since a [Slop] doesn't know where its KVs came from, the violations have
+    /// no line numbers. To get them, see [SlopSchema::validate_document].
+    pub fn validate(&self, slop: &Slop) -> Result<(), Vec<Violation>> {
+        let mut kvs: Vec<_> = slop.iter().map(|(k, v)| (k.as_str(), v, None)).collect();
+        kvs.sort_by(|a, b| a.0.cmp(b.0));
+        self.validate_kvs(kvs)
+    }
+
+    /// Same as [SlopSchema::validate], but includes line numbers in the
+    /// violations. Violations are sorted by key.
+    ///
+    /// If a key appears more than once, only the last KV is checked, since it
+    /// is the one that is kept when parsing.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{SlopDocument, schema::SlopSchema};
+    ///
+    /// let schema: SlopSchema = "\
+    ///     tags.pattern=^[a-z]+$
+    /// ".parse().unwrap();
+    /// let doc: SlopDocument = "# Tags\ntags{\nok\nNOT OK\n}".parse().unwrap();
+    /// let violations = schema.validate_document(&doc).unwrap_err();
+    ///
+    /// assert_eq!(violations[0].line, Some(3));
+    /// ```
+    pub fn validate_document(&self, doc: &SlopDocument) -> Result<(), Vec<Violation>> {
+        let mut last = BTreeMap::new();
+
+        for kv in doc.kvs() {
+            last.insert(kv.key(), (kv.value(), kv.line()));
+        }
+
+        self.validate_kvs(last.into_iter().map(|(k, (v, l))| (k, v, l)).collect())
+    }
+
+    // Checks KVs that are sorted by key, along with the line they start in.
+    fn validate_kvs(&self, kvs: Vec<(&str, &SlopValue, Option<usize>)>)
+        -> Result<(), Vec<Violation>>
+    {
+        let mut violations = vec![];
+
+        for (key, key_schema) in &self.keys {
+            if key_schema.required && kvs.binary_search_by(|kv| kv.0.cmp(key)).is_err() {
+                violations.push(Violation {
+                    key: key.clone(),
+                    line: None,
+                    kind: ViolationKind::Missing,
+                });
+            }
+        }
+
+        for (key, value, line) in kvs {
+            let mut push = |line: Option<usize>, kind| violations.push(Violation {
+                key: key.to_string(),
+                line,
+                kind,
+            });
+
+            let Some(key_schema) = self.keys.get(key) else {
+                if !self.allow_unknown_keys {
+                    push(line, ViolationKind::Unknown);
+                }
+                continue;
+            };
+
+            if let Some(kind) = key_schema.kind {
+                if kind != ValueKind::of(value) {
+                    push(line, ViolationKind::WrongKind(kind));
+                    continue;
+                }
+            }
+
+            match value {
+                SlopValue::String(s) => {
+                    key_schema.check_value(s).into_iter().for_each(|k| push(line, k));
+                },
+                SlopValue::List(l) => {
+                    if key_schema.min_items.is_some_and(|min| l.len() < min) {
+                        push(line, ViolationKind::TooFewItems(l.len()));
+                    }
+
+                    if key_schema.max_items.is_some_and(|max| l.len() > max) {
+                        push(line, ViolationKind::TooManyItems(l.len()));
+                    }
+
+                    for (i, item) in l.iter().enumerate() {
+                        // List items are written one per line, after the key.
+                        let item_line = line.map(|l| l + 1 + i);
+                        key_schema.check_value(item).into_iter().for_each(|k| push(item_line, k));
+                    }
+                },
+            }
+        }
+
+        violations.sort_by(|a, b| a.key.cmp(&b.key));
+
+        if violations.is_empty() { Ok(()) } else { Err(violations) }
+    }
+}
+
+impl KeySchema {
+    // Returns the violations of the value checks for a single string.
+    fn check_value(&self, value: &str) -> Vec<ViolationKind> {
+        let mut violations = vec![];
+
+        if self.pattern.as_ref().is_some_and(|p| !p.is_match(value)) {
+            violations.push(ViolationKind::PatternMismatch(value.to_string()));
+        }
+
+        if self.min.is_some() || self.max.is_some() {
+            // `NaN` would pass every comparison, and `inf` is never meant.
+            match value.trim().parse::<f64>() {
+                Ok(n) if !n.is_finite() => {
+                    violations.push(ViolationKind::NotANumber(value.to_string()));
+                },
+                Ok(n) if self.min.is_some_and(|min| n < min)
+                    || self.max.is_some_and(|max| n > max) =>
+                {
+                    violations.push(ViolationKind::OutOfRange(value.to_string()));
+                },
+                Ok(_) => {},
+                Err(_) => violations.push(ViolationKind::NotANumber(value.to_string())),
+            }
+        }
+
+        if self.allowed.as_ref().is_some_and(|a| !a.iter().any(|v| v == value)) {
+            violations.push(ViolationKind::NotAllowed(value.to_string()));
+        }
+
+        violations
+    }
+}
+
+impl Default for SlopSchema {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FromStr for SlopSchema {
+    type Err = SlopError;
+
+    /// Parses a SLOP string and loads it as a schema.
+    ///
+    /// See the [module documentation](self) for the format.
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_slop(&s.parse()?)
+    }
+}
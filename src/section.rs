@@ -0,0 +1,448 @@
+//! Defines views over the KVs that share a key prefix, and the [SlopTree].
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! SLOP has no nesting, so nested data is usually written with dotted keys,
+//! such as `server.http.port`. A section is the set of KVs whose keys start
+//! with a prefix and a separator: the `server.http` section has a `port` KV,
+//! and the `server` section has an `http` child section.
+//!
+//! The separator is `.` unless stated otherwise.
+
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::{
+    Slop,
+    SlopValue,
+    error::{SlopError, SlopResult},
+};
+
+const DEFAULT_SEPARATOR: &str = ".";
+
+impl Slop {
+    /// Returns a view over the KVs in the section `prefix`, separated by `.`.
+    /// An empty prefix returns a view over the whole [Slop].
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let slop_str = "\
+    ///     server.name=main
+    ///     server.http.port=8080
+    ///     server.http.tls.cert=cert.pem
+    ///     server.ftp.port=21
+    /// ";
+    /// let slop: Slop = slop_str.parse().unwrap();
+    /// let server = slop.section("server");
+    ///
+    /// assert_eq!(server.get("name"), Some(&"main".into()));
+    /// assert_eq!(server.section("http").get("port"), Some(&"8080".into()));
+    /// assert_eq!(server.child_sections().collect::<Vec<_>>(), ["ftp", "http"]);
+    /// ```
+    #[inline(always)]
+    pub fn section(&self, prefix: &str) -> Section<'_> {
+        self.section_with_separator(prefix, DEFAULT_SEPARATOR)
+    }
+
+    /// Same as [Slop::section], but with a custom separator.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the separator is empty.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let slop: Slop = "server/http/port=8080".parse().unwrap();
+    /// let http = slop.section_with_separator("server/http", "/");
+    ///
+    /// assert_eq!(http.get("port"), Some(&"8080".into()));
+    /// ```
+    pub fn section_with_separator(&self, prefix: &str, separator: &str) -> Section<'_> {
+        assert!(!separator.is_empty(), "the separator is empty");
+
+        Section {
+            slop: self,
+            prefix: prefix_of(prefix, separator),
+            separator: separator.to_string(),
+        }
+    }
+
+    /// Same as [Slop::section], but allows inserting and removing KVs through
+    /// the view.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let mut slop: Slop = "server.http.port=8080".parse().unwrap();
+    /// let mut http = slop.section_mut("server.http");
+    ///
+    /// http.insert("host".to_string(), "localhost").unwrap();
+    /// http.remove("port");
+    ///
+    /// assert_eq!(slop.get("server.http.host"), Some(&"localhost".into()));
+    /// assert!(!slop.contains_key("server.http.port"));
+    /// ```
+    #[inline(always)]
+    pub fn section_mut(&mut self, prefix: &str) -> SectionMut<'_> {
+        self.section_mut_with_separator(prefix, DEFAULT_SEPARATOR)
+    }
+
+    /// Same as [Slop::section_mut], but with a custom separator.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the separator is empty.
+    pub fn section_mut_with_separator(&mut self, prefix: &str, separator: &str)
+        -> SectionMut<'_>
+    {
+        assert!(!separator.is_empty(), "the separator is empty");
+
+        SectionMut {
+            prefix: prefix_of(prefix, separator),
+            separator: separator.to_string(),
+            slop: self,
+        }
+    }
+
+    /// Converts the [Slop] into a [SlopTree], splitting the keys at `.`.
+    ///
+    /// See also: [Section::to_tree]
+    #[inline(always)]
+    pub fn to_tree(&self) -> SlopTree {
+        self.section("").to_tree()
+    }
+}
+
+/// A read-only view over the KVs in a section of a [Slop]. (See
+/// [section](crate::section))
+///
+/// The keys of the view have the prefix and separator stripped.
+#[derive(Debug, Clone)]
+pub struct Section<'a> {
+    slop: &'a Slop,
+    prefix: String,
+    separator: String,
+}
+
+impl<'a> Section<'a> {
+    /// Returns the prefix of the section, without the trailing separator.
+    pub fn prefix(&self) -> &str {
+        self.prefix.strip_suffix(&self.separator).unwrap_or(&self.prefix)
+    }
+
+    /// Returns the [SlopValue] associated with the provided key (without the
+    /// prefix), or [None] if no such KV exists.
+    #[inline(always)]
+    pub fn get(&self, key: &str) -> Option<&'a SlopValue> {
+        self.slop.get(&self.full_key(key))
+    }
+
+    /// Same as [Slop::get_string], but for a key in the section.
+    #[inline(always)]
+    pub fn get_string(&self, key: &str) -> Option<&'a String> {
+        self.get(key)?.string()
+    }
+
+    /// Same as [Slop::get_list], but for a key in the section.
+    #[inline(always)]
+    pub fn get_list(&self, key: &str) -> Option<&'a Vec<String>> {
+        self.get(key)?.list()
+    }
+
+    /// Returns `true` if the section contains the provided key (without the
+    /// prefix).
+    #[inline(always)]
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.slop.contains_key(&self.full_key(key))
+    }
+
+    /// Returns `true` if the section has no KVs, including in its child
+    /// sections.
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Iterates over the KVs in the section and its child sections in
+    /// arbitrary order, with the prefix stripped from the keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a SlopValue)> + '_ {
+        self.slop
+            .iter()
+            .filter_map(|(k, v)| Some((k.strip_prefix(&self.prefix)?, v)))
+    }
+
+    /// Iterates over the names of the child sections in sorted order.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let slop: Slop = "a.x=1\nb.y.z=2\nb.w=3\nc=4".parse().unwrap();
+    ///
+    /// assert_eq!(slop.section("").child_sections().collect::<Vec<_>>(), ["a", "b"]);
+    /// assert_eq!(slop.section("b").child_sections().collect::<Vec<_>>(), ["y"]);
+    /// ```
+    pub fn child_sections(&self) -> impl Iterator<Item = &'a str> {
+        self.iter()
+            .filter_map(|(k, _)| Some(k.split_once(self.separator.as_str())?.0))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+    }
+
+    /// Returns a view over a child section.
+    pub fn section(&self, name: &str) -> Section<'a> {
+        Section {
+            slop: self.slop,
+            prefix: format!("{}{name}{}", self.prefix, self.separator),
+            separator: self.separator.clone(),
+        }
+    }
+
+    /// Copies the KVs in the section into a new [Slop], with the prefix
+    /// stripped from the keys.
+    pub fn to_slop(&self) -> Slop {
+        let mut slop = Slop::new();
+
+        for (key, value) in self.iter() {
+            slop.insert_unchecked(key.to_string(), value.clone());
+        }
+
+        slop
+    }
+
+    /// Converts the section into a [SlopTree], splitting the keys at the
+    /// separator.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let slop: Slop = "server.http.port=8080\nserver.http=on".parse().unwrap();
+    /// let tree = slop.section("server").to_tree();
+    /// let http = &tree.children["http"];
+    ///
+    /// assert_eq!(http.value, Some("on".into()));
+    /// assert_eq!(http.children["port"].value, Some("8080".into()));
+    /// ```
+    pub fn to_tree(&self) -> SlopTree {
+        let mut tree = SlopTree::new();
+
+        for (key, value) in self.iter() {
+            let node = key
+                .split(self.separator.as_str())
+                .fold(&mut tree, |node, name| node.children.entry(name.to_string()).or_default());
+            node.value = Some(value.clone());
+        }
+
+        tree
+    }
+
+    #[inline(always)]
+    fn full_key(&self, key: &str) -> String {
+        format!("{}{key}", self.prefix)
+    }
+}
+
+/// A view over the KVs in a section of a [Slop], which can insert and remove
+/// KVs. (See [section](crate::section))
+///
+/// The keys of the view have the prefix and separator stripped.
+#[derive(Debug)]
+pub struct SectionMut<'a> {
+    slop: &'a mut Slop,
+    prefix: String,
+    separator: String,
+}
+
+impl SectionMut<'_> {
+    /// Returns a read-only view over the same section.
+    pub fn as_section(&self) -> Section<'_> {
+        Section {
+            slop: self.slop,
+            prefix: self.prefix.clone(),
+            separator: self.separator.clone(),
+        }
+    }
+
+    /// Returns the [SlopValue] associated with the provided key (without the
+    /// prefix), or [None] if no such KV exists.
+    #[inline(always)]
+    pub fn get(&self, key: &str) -> Option<&SlopValue> {
+        self.slop.get(&format!("{}{key}", self.prefix))
+    }
+
+    /// Same as [Slop::insert], but for a key in the section.
+    #[inline(always)]
+    pub fn insert<V: Into<SlopValue>>(&mut self, key: String, value: V)
+        -> SlopResult<Option<SlopValue>>
+    {
+        self.slop.insert(format!("{}{key}", self.prefix), value)
+    }
+
+    /// Same as [Slop::remove], but for a key in the section.
+    #[inline(always)]
+    pub fn remove(&mut self, key: &str) -> Option<SlopValue> {
+        self.slop.remove(&format!("{}{key}", self.prefix))
+    }
+
+    /// Returns a view over a child section.
+    pub fn section_mut(&mut self, name: &str) -> SectionMut<'_> {
+        SectionMut {
+            prefix: format!("{}{name}{}", self.prefix, self.separator),
+            separator: self.separator.clone(),
+            slop: self.slop,
+        }
+    }
+
+    /// Removes every KV in the section and its child sections.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let mut slop: Slop = "a.x=1\na.y.z=2\nb=3".parse().unwrap();
+    /// slop.section_mut("a").clear();
+    ///
+    /// assert_eq!(slop, "b=3".parse().unwrap());
+    /// ```
+    pub fn clear(&mut self) {
+        let keys: Vec<_> = self
+            .as_section()
+            .iter()
+            .map(|(k, _)| format!("{}{k}", self.prefix))
+            .collect();
+
+        for key in keys {
+            self.slop.remove(&key);
+        }
+    }
+}
+
+/// A nested representation of a [Slop], where each `.`-separated part of a
+/// key is a level of nesting.
+///
+/// Since a key such as `a` can hold a value while `a.b` also exists, every
+/// node can have both a value and children. This makes the conversion
+/// lossless in both directions.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{Slop, SlopTree};
+///
+/// let mut tree = SlopTree::new();
+/// let http = tree.children.entry("http".to_string()).or_default();
+/// http.children.entry("port".to_string()).or_default().value = Some("8080".into());
+///
+/// let slop = tree.to_slop(".").unwrap();
+///
+/// assert_eq!(slop.get("http.port"), Some(&"8080".into()));
+/// assert_eq!(slop.to_tree(), tree);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlopTree {
+    /// The value of the key that leads to this node, if it has one.
+    pub value: Option<SlopValue>,
+    /// The child nodes, by the name of the part of the key that leads to them.
+    pub children: BTreeMap<String, SlopTree>,
+}
+
+impl SlopTree {
+    /// Constructs a [SlopTree] without a value or children.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Flattens the tree into a new [Slop], joining the names of the nodes
+    /// with `separator`. The value of the root node is ignored, since it
+    /// doesn't have a key.
+    ///
+    /// Returns [SlopError::InvalidKey] if the name of a node contains the
+    /// separator, since its key could be the same as the key of another node,
+    /// and a [SlopError] if a resulting key or value can't be written to a
+    /// SLOP string.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the separator is empty.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{SlopTree, error::SlopError};
+    ///
+    /// let mut tree = SlopTree::new();
+    /// let a = tree.children.entry("a".to_string()).or_default();
+    /// a.children.entry("b.c".to_string()).or_default().value = Some("1".into());
+    ///
+    /// assert!(matches!(tree.to_slop("."), Err(SlopError::InvalidKey { .. })));
+    /// assert!(tree.to_slop("/").is_ok());
+    /// ```
+    pub fn to_slop(&self, separator: &str) -> SlopResult<Slop> {
+        assert!(!separator.is_empty(), "the separator is empty");
+
+        let mut slop = Slop::new();
+        self.flatten("", separator, &mut slop)?;
+        Ok(slop)
+    }
+
+    fn flatten(&self, prefix: &str, separator: &str, slop: &mut Slop) -> SlopResult<()> {
+        for (name, child) in &self.children {
+            let key = format!("{prefix}{name}");
+
+            if name.contains(separator) {
+                return Err(SlopError::InvalidKey { key });
+            }
+
+            if let Some(value) = &child.value {
+                slop.insert(key.clone(), value.clone())?;
+            }
+
+            child.flatten(&format!("{key}{separator}"), separator, slop)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<SlopTree> for Slop {
+    type Error = SlopError;
+
+    /// Same as [SlopTree::to_slop], with `.` as the separator.
+    #[inline(always)]
+    fn try_from(tree: SlopTree) -> Result<Self, Self::Error> {
+        tree.to_slop(DEFAULT_SEPARATOR)
+    }
+}
+
+impl From<&Slop> for SlopTree {
+    /// Same as [Slop::to_tree].
+    #[inline(always)]
+    fn from(slop: &Slop) -> Self {
+        slop.to_tree()
+    }
+}
+
+// Returns the prefix that the keys of the section start with.
+fn prefix_of(prefix: &str, separator: &str) -> String {
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{prefix}{separator}")
+    }
+}
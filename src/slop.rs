@@ -3,16 +3,31 @@
 //! Eveything in this module is publically imported into [crate], so you can
 //! just import them from there.
 
-use std::{
-    collections::{HashMap, hash_map},
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+#[cfg(not(feature = "std"))]
+use alloc::collections::{BTreeMap as Map, btree_map as map};
+use core::{
     str::FromStr,
+    fmt::Display,
+    iter::FusedIterator,
+};
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap as Map, hash_map as map},
     path::Path,
     fs,
-    fmt::Display,
 };
 
 use crate::{
+    FormatOptions,
+    SlopDocument,
     SlopValue,
+    encoding::BOM,
     error::{SlopError, SlopResult},
 };
 
@@ -37,29 +52,38 @@ use crate::{
 /// assert_eq!(slop.get("other-key"), Some(&vec!["other", "value"].into()));
 /// assert_eq!(slop.get("invalid key"), None);
 /// ```
+///
+/// The KVs are kept in a [HashMap](std::collections::HashMap), or in a
+/// [BTreeMap](alloc::collections::BTreeMap) without the `std` feature.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Slop {
-    items: HashMap<String, SlopValue>,
+    items: Map<String, SlopValue>,
 }
 
 impl Slop {
     /// Constructs an empty [Slop].
     pub fn new() -> Self {
-        Self { items: HashMap::new() }
+        Self { items: Map::new() }
     }
 
     /// Reads the contents of a file, parses it as a SLOP string, then returns a
     /// new [Slop] with the resulting items.
-    #[inline(always)]
+    ///
+    /// The file may be UTF-8 or UTF-16, with or without a BOM. (See
+    /// [decode](crate::decode))
+    ///
+    /// `#!include` directives are comments to this function, so only the file
+    /// itself is read. To follow them, see [Slop::open_with].
+    #[cfg(feature = "std")]
     pub fn open<P: AsRef<Path>>(path: P) -> SlopResult<Self> {
-        fs::read_to_string(path)?.parse()
+        let named = |e: SlopError| e.with_path(&path);
+        let bytes = fs::read(&path).map_err(|e| named(e.into()))?;
+        Self::from_bytes(&bytes).map_err(named)
     }
 
     /// Iterates over the [Slop]'s KVs in arbitrary order.
     /// The iterator element type is `(&'a String, &'a SlopValue)`.
     /// 
-    /// This is the same iterator type returned by [HashMap::iter].
-    /// 
     /// ## Examples
     /// 
     /// ```
@@ -71,21 +95,20 @@ impl Slop {
     ///     c=3
     /// ";
     /// let slop: Slop = slop_str.parse().unwrap();
+    /// assert_eq!(slop.iter().len(), 3);
     /// 
     /// for (key, value) in slop.iter() {
     ///     println!("key: {key} val: {value:?}");
     /// }
     /// ```
-    pub fn iter(&self) -> hash_map::Iter<'_, String, SlopValue> {
-        self.items.iter()
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.items.iter())
     }
 
     /// Iterates over the [Slop]'s KVs in arbitrary order,
     /// with mutable references to the values.
     /// The iterator element type is `(&'a String, &'a mut SlopValue)`.
     /// 
-    /// This is the same iterator type returned by [HashMap::iter_mut].
-    /// 
     /// ## Examples
     /// 
     /// ```
@@ -112,8 +135,8 @@ impl Slop {
     ///     println!("key: {key} val: {value:?}");
     /// }
     /// ```
-    pub fn iter_mut(&mut self) -> hash_map::IterMut<'_, String, SlopValue> {
-        self.items.iter_mut()
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.items.iter_mut())
     }
 
     /// Returns `true` if the [Slop] is empty.
@@ -189,9 +212,10 @@ impl Slop {
     ///
     /// Returns the previous value, or [None] if no such KV existed before.
     /// 
-    /// Returns a [SlopError] if the key contains `=` or ends in `{`, as these
-    /// keys would produce an invalid SLOP string. \
-    /// If you know for a fact that the key is valid, you can use
+    /// Returns [SlopError::InvalidKey] or [SlopError::InvalidValue] if the KV
+    /// wouldn't be read back unchanged from a saved file, with the same rules
+    /// as [Slop::validate]. \
+    /// If you know for a fact that the KV is valid, you can use
     /// [Slop::insert_unchecked] instead.
     /// 
     /// ## Examples
@@ -208,21 +232,37 @@ impl Slop {
     /// let prev_value = slop.insert("this key = bad".to_string(), "value");
     /// assert!(prev_value.is_err());
     /// assert_eq!(slop.get("this key = bad"), None);
+    ///
+    /// // These would be read back as a comment and as `key`.
+    /// assert!(slop.insert("#hidden".to_string(), "value").is_err());
+    /// assert!(slop.insert("  key".to_string(), "value").is_err());
+    /// assert!(slop.insert("list".to_string(), vec!["  item"]).is_err());
     /// ```
     pub fn insert<V: Into<SlopValue>>(&mut self, key: String, value: V)
         -> SlopResult<Option<SlopValue>>
     {
-        if key.chars().any(|c| c == '=') || key.ends_with('{') {
-            Err(SlopError::InvalidKey(key))
+        let value = value.into();
+        check_exact(&key, &value)?;
+        Ok(self.items.insert(key, value))
+    }
+
+    // Inserts a KV read by a parser, which has already cleaned it up, so only
+    // the key is checked.
+    pub(crate) fn insert_parsed<V: Into<SlopValue>>(&mut self, key: String, value: V)
+        -> SlopResult<Option<SlopValue>>
+    {
+        if !is_valid_key(&key) {
+            Err(SlopError::InvalidKey { key })
         } else {
             Ok(self.items.insert(key, value.into()))
         }
     }
 
     /// A variation of [Slop::insert] that doesn't check whether the key
-    /// is valid.
+    /// and the value are valid.
     /// 
-    /// Use if you know ahead of time that the key is always valid.
+    /// Use if you know ahead of time that the KV is always valid, or if the
+    /// [Slop] is never saved.
     /// 
     /// ## Examples
     /// 
@@ -241,6 +281,25 @@ impl Slop {
         self.items.insert(key, value.into())
     }
 
+    /// Removes the KV defined by `key`.
+    ///
+    /// Returns the removed value, or [None] if no such KV existed.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let mut slop: Slop = "a=1\nb=2".parse().unwrap();
+    ///
+    /// assert_eq!(slop.remove("a"), Some("1".into()));
+    /// assert_eq!(slop.remove("a"), None);
+    /// assert!(!slop.contains_key("a"));
+    /// ```
+    pub fn remove(&mut self, key: &str) -> Option<SlopValue> {
+        self.items.remove(key)
+    }
+
     /// Parses the provided SLOP string and appends the results.
     ///
     /// If you are creating the [Slop] just before parsing, consider
@@ -277,6 +336,17 @@ impl Slop {
     /// ```
     pub fn append_slop_string(&mut self, slop_str: &str) -> Result<(), SlopError>
     {
+        self.append_with_includes(slop_str, &mut |_, _| Ok(()))
+    }
+
+    // Same as `append_slop_string`, but calls `include` with the path of every
+    // `#!include` directive, at the point where it appears.
+    pub(crate) fn append_with_includes(
+        &mut self,
+        slop_str: &str,
+        include: &mut dyn FnMut(&mut Slop, &str) -> SlopResult<()>,
+    ) -> SlopResult<()> {
+        let slop_str = slop_str.strip_prefix(BOM).unwrap_or(slop_str);
         let lines: Vec<&str> = slop_str.split('\n').collect();
         let mut skip_lines = 0usize;
 
@@ -289,57 +359,121 @@ impl Slop {
             // SAFETY: `i` is always in range.
             let line = clean_up_line(lines[i]);
 
+            if let Some(path) = parse_include(line) {
+                include(self, path)?;
+                continue;
+            }
+
             if line.is_empty() || line.starts_with('#') {
                 continue;
             }
 
             if let Some((key, value)) = parse_string_kv(line) {
-                self.insert(key.to_string(), value)?;
+                self.insert_parsed(key.to_string(), value).map_err(in_line(i, lines[i]))?;
             } else if let Some((key, value, skip))
                 = parse_list_kv(&lines, i)?
             {
-                self.insert(key.to_string(), value)?;
+                self.insert_parsed(key.to_string(), value).map_err(in_line(i, lines[i]))?;
                 skip_lines = skip;
             } else {
-                return Err(SlopError::InvalidLine(i, line.to_string()));
+                return Err(SlopError::InvalidLine { line: i, text: strip_cr(lines[i]).to_string() });
             }
         }
 
         Ok(())
     }
 
+    /// Checks that writing the [Slop] to a SLOP string (with [Slop::to_string],
+    /// [Slop::to_string_pretty] or [Slop::to_string_formatted]) and parsing it
+    /// back gives the same [Slop].
+    ///
+    /// Returns [SlopError::InvalidKey] if a key contains `=` or a line break,
+    /// starts with whitespace or `#`, or ends in `{`. Returns
+    /// [SlopError::InvalidValue] if a value contains a line break or ends in
+    /// `\r`, or if a list item starts with whitespace or is `}`. If several
+    /// KVs are invalid, which one is reported is arbitrary.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::Slop;
+    ///
+    /// let mut slop = Slop::new();
+    /// slop.insert("a".to_string(), vec!["#not a comment", "b=c", ""]).unwrap();
+    ///
+    /// assert!(slop.validate().is_ok());
+    /// assert_eq!(slop.to_string().parse::<Slop>().unwrap(), slop);
+    ///
+    /// // The leading whitespace would be removed by the parser.
+    /// slop.insert_unchecked("a".to_string(), vec!["  indented"]);
+    /// assert!(slop.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> SlopResult<()> {
+        self.items.iter().try_for_each(|(key, value)| check_exact(key, value))
+    }
+
     /// Same as [Slop::to_string], but indents the values of lists. Uses 4
     /// spaces for indentation.
     pub fn to_string_pretty(&self) -> String {
         self.items.iter().fold(String::new(), |mut acc, (k, v)| {
-            acc.push_str(&k);
+            acc.push_str(k);
             acc.push_str(&v.to_string_pretty());
             acc.push('\n');
             acc
         })
     }
 
+    /// Returns the [Slop] as a SLOP string in the canonical style described in
+    /// [FormatOptions].
+    ///
+    /// Unless [FormatOptions::sort_keys] is set, the KVs are written in
+    /// arbitrary order. To format a SLOP string while keeping its comments,
+    /// see [format](crate::format).
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{FormatOptions, Slop};
+    ///
+    /// let slop: Slop = "b=2\na{\nx\n}".parse().unwrap();
+    /// let options = FormatOptions { sort_keys: true, ..Default::default() };
+    ///
+    /// assert_eq!(slop.to_string_formatted(&options), "a{\n    x\n}\nb=2\n");
+    /// ```
+    pub fn to_string_formatted(&self, options: &FormatOptions) -> String {
+        SlopDocument::from(self.clone()).to_string_formatted(options)
+    }
+
     /// Converts the [Slop] into a SLOP string and writes it to the text file at
     /// the provided path.
     /// 
     /// If you want the list values to be indented, see [Slop::save_pretty].
+    #[cfg(feature = "std")]
     #[inline(always)]
     pub fn save<P: AsRef<Path>>(&self, path: P) -> SlopResult<()> {
-        Ok(fs::write(path, self.to_string())?)
+        fs::write(&path, self.to_string()).map_err(|e| SlopError::from(e).with_path(path))
     }
 
     /// Same as [Slop::save], but indents the values of lists. Uses 4
     /// spaces for indentation.
+    #[cfg(feature = "std")]
     #[inline(always)]
     pub fn save_pretty<P: AsRef<Path>>(&self, path: P) -> SlopResult<()> {
-        Ok(fs::write(path, self.to_string_pretty())?)
+        fs::write(&path, self.to_string_pretty()).map_err(|e| SlopError::from(e).with_path(path))
+    }
+}
+
+impl Default for Slop {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl Display for Slop {
     /// Displays the [Slop] as a valid SLOP string. For a pretty-print version,
     /// see [Slop::to_string_pretty].
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}", self.items.iter().fold(String::new(), |mut acc, (k, v)| {
             acc.push_str(k);
             acc.push_str(&v.to_string());
@@ -365,10 +499,10 @@ impl FromStr for Slop {
 
 impl IntoIterator for Slop {
     type Item = (String, SlopValue);
-    type IntoIter = <HashMap<String, SlopValue> as IntoIterator>::IntoIter;
+    type IntoIter = IntoIter;
 
-    /// Creates a consuming iterator out of the [Slop]'s KVs.
-    /// This is the same iterator type as the one from [HashMap::into_iter].
+    /// Creates a consuming iterator out of the [Slop]'s KVs, in arbitrary
+    /// order.
     /// 
     /// ```
     /// use slop_rs::{Slop, SlopValue};
@@ -385,19 +519,132 @@ impl IntoIterator for Slop {
     /// let vec: Vec<(String, SlopValue)> = slop.into_iter().collect();
     /// ```
     fn into_iter(self) -> Self::IntoIter {
-        self.items.into_iter()
+        IntoIter(self.items.into_iter())
+    }
+}
+
+/// An iterator over the KVs of a [Slop], returned by [Slop::iter].
+///
+/// The KVs are in arbitrary order, which may change with the `std` feature.
+#[derive(Debug, Clone)]
+pub struct Iter<'a>(map::Iter<'a, String, SlopValue>);
+
+/// An iterator over the KVs of a [Slop] with mutable references to the values,
+/// returned by [Slop::iter_mut].
+#[derive(Debug)]
+pub struct IterMut<'a>(map::IterMut<'a, String, SlopValue>);
+
+/// A consuming iterator over the KVs of a [Slop], returned by its
+/// [into_iter](IntoIterator::into_iter).
+#[derive(Debug)]
+pub struct IntoIter(map::IntoIter<String, SlopValue>);
+
+// The three iterators only forward to the map's ones, which are all exact and
+// fused.
+macro_rules! forward_iterator {
+    ($iter:ty, $item:ty) => {
+        impl<'a> Iterator for $iter {
+            type Item = $item;
+
+            #[inline(always)]
+            fn next(&mut self) -> Option<Self::Item> {
+                self.0.next()
+            }
+
+            #[inline(always)]
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.0.size_hint()
+            }
+        }
+
+        impl<'a> ExactSizeIterator for $iter {
+            #[inline(always)]
+            fn len(&self) -> usize {
+                self.0.len()
+            }
+        }
+
+        impl<'a> FusedIterator for $iter {}
+    };
+}
+
+forward_iterator!(Iter<'a>, (&'a String, &'a SlopValue));
+forward_iterator!(IterMut<'a>, (&'a String, &'a mut SlopValue));
+forward_iterator!(IntoIter, (String, SlopValue));
+
+// Returns `true` if the key can be written to a SLOP string, that is, if it
+// doesn't contain `=` and doesn't end in `{`.
+#[inline]
+pub(crate) fn is_valid_key(key: &str) -> bool {
+    !key.contains('=') && !key.ends_with('{')
+}
+
+// Returns [SlopError::InvalidKey] or [SlopError::InvalidValue] if the KV is not
+// read back unchanged from a SLOP string. (See [Slop::validate])
+pub(crate) fn check_exact(key: &str, value: &SlopValue) -> SlopResult<()> {
+    if !is_exact_key(key) {
+        return Err(SlopError::InvalidKey { key: key.to_string() });
     }
+
+    value.validate()
+}
+
+// Returns `true` if the key is read back unchanged from a SLOP string.
+// Besides being valid, it can't break the line, have its leading whitespace
+// removed, turn the line into a comment, or be taken for a BOM.
+pub(crate) fn is_exact_key(key: &str) -> bool {
+    is_valid_key(key)
+        && !key.contains('\n')
+        && !key.starts_with(|c: char| c.is_whitespace() || c == '#' || c == BOM)
+}
+
+// Returns `true` if the value of a string KV is read back unchanged.
+pub(crate) fn is_exact_string(value: &str) -> bool {
+    !value.contains('\n') && !value.ends_with('\r')
+}
+
+// Returns `true` if the list item is read back unchanged, that is, if cleaning
+// it up changes nothing and it doesn't close the list.
+pub(crate) fn is_exact_item(item: &str) -> bool {
+    !item.contains('\n') && clean_up_line(item) == item && item != "}"
 }
 
 // Removes leading (not trailing) whitespace and a potential trailing `\r`.
 // This function is zero-copy.
 #[inline]
-fn clean_up_line(line: &str) -> &str {
-    line.strip_suffix('\r').unwrap_or(line).trim_start()
+pub(crate) fn clean_up_line(line: &str) -> &str {
+    strip_cr(line).trim_start()
+}
+
+// Returns a function that wraps an error about the KV defined in the line in a
+// [SlopError::InLine].
+pub(crate) fn in_line(index: usize, line: &str) -> impl FnOnce(SlopError) -> SlopError + '_ {
+    move |e| SlopError::InLine {
+        line: index,
+        text: strip_cr(line).to_string(),
+        error: Box::new(e),
+    }
+}
+
+// Removes the `\r` of a CRLF line ending.
+pub(crate) fn strip_cr(line: &str) -> &str {
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+// Returns the path of the `#!include` directive, or [None] if the line is not
+// one. The line must be cleaned up. (See [crate::include::directive])
+pub(crate) fn parse_include(line: &str) -> Option<&str> {
+    let path = line.strip_prefix("#!include")?;
+
+    if path.starts_with([' ', '\t']) {
+        Some(path.trim())
+    } else {
+        None
+    }
 }
 
 // Returns the parsed KV, or [None] if the line does not define a string KV.
-fn parse_string_kv(line: &str) -> Option<(&str, SlopValue)> {
+pub(crate) fn parse_string_kv(line: &str) -> Option<(&str, SlopValue)> {
     let (key, value) = line.split_once('=')?;
     Some((key, value.into()))
 }
@@ -408,7 +655,7 @@ fn parse_string_kv(line: &str) -> Option<(&str, SlopValue)> {
 // ## Panics
 //
 // Panics if `start_index` or `start_index + 1` is not in the range of `lines`.
-fn parse_list_kv<'a>(lines: &'a Vec<&'a str>, start_index: usize)
+pub(crate) fn parse_list_kv<'a>(lines: &[&'a str], start_index: usize)
     -> Result<Option<(&'a str, SlopValue, usize)>, SlopError>
 {
     let key = if let Some(k) = clean_up_line(lines[start_index]).strip_suffix('{') {
@@ -419,8 +666,8 @@ fn parse_list_kv<'a>(lines: &'a Vec<&'a str>, start_index: usize)
 
     let mut values = vec![];
 
-    for i in (start_index + 1)..lines.len() {
-        let line = clean_up_line(lines[i]);
+    for (i, line) in lines.iter().enumerate().skip(start_index + 1) {
+        let line = clean_up_line(line);
 
         if line == "}" {
             return Ok(Some((key, values.into(), i - start_index)));
@@ -429,5 +676,8 @@ fn parse_list_kv<'a>(lines: &'a Vec<&'a str>, start_index: usize)
         values.push(line.to_string());
     }
 
-    Err(SlopError::UnclosedList(start_index, lines[start_index].to_string()))
+    Err(SlopError::UnclosedList {
+        line: start_index,
+        text: strip_cr(lines[start_index]).to_string(),
+    })
 }
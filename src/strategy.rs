@@ -0,0 +1,121 @@
+//! Defines [proptest] strategies that generate [Slop]s and [SlopValue]s, for
+//! property-testing code that reads or writes SLOP. Requires the `proptest`
+//! feature.
+//!
+//! Everything generated by these strategies passes [Slop::validate], so it
+//! can be saved and parsed back unchanged. The strategies favor the cases
+//! that are easy to get wrong: empty keys, values and lists, list items that
+//! look like comments, KVs or the end of the list, `=`, `{` and `}` in
+//! values, and trailing whitespace.
+//!
+//! [Slop] and [SlopValue] also implement [Arbitrary], so `any::<Slop>()` is the
+//! same as [slop()].
+//!
+//! ## Examples
+//!
+//! ```
+//! use proptest::prelude::*;
+//! use slop_rs::Slop;
+//!
+//! proptest! {
+//!     fn round_trip(slop in any::<Slop>()) {
+//!         prop_assert_eq!(slop.to_string().parse::<Slop>().unwrap(), slop);
+//!     }
+//! }
+//!
+//! round_trip();
+//! ```
+
+use proptest::{
+    arbitrary::Arbitrary,
+    collection::{hash_map, vec},
+    prelude::*,
+    strategy::BoxedStrategy,
+};
+
+use crate::{
+    Slop,
+    SlopValue,
+    slop::{is_exact_item, is_exact_key, is_exact_string},
+};
+
+/// Generates keys that pass [Slop::validate], including the empty key and
+/// keys with whitespace, `#` and braces.
+pub fn key() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => "[a-z][a-z0-9_.-]{0,8}",
+        1 => "[a-z0-9 \t#{}\r\u{3000}-]{0,8}",
+    ]
+    .prop_filter("the key must be read back unchanged", |k| is_exact_key(k))
+}
+
+/// Generates values of string KVs that pass [Slop::validate], including
+/// empty values and values with `=`, `#`, braces and surrounding whitespace.
+pub fn string() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => "[a-z0-9 ]{0,12}",
+        1 => "[a-z0-9 \t#={}\r\u{3000}-]{0,12}",
+    ]
+    .prop_filter("the value must be read back unchanged", |s| is_exact_string(s))
+}
+
+/// Generates list items that pass [Slop::validate], including empty items and
+/// items that look like comments, KVs, directives or the start or end of a
+/// list.
+pub fn item() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(String::new()),
+        Just("#!include other.slop".to_string()),
+        "#[a-z0-9 =]{0,8}",
+        "[a-z]{1,4}=[a-z0-9 =]{0,6}",
+        "[a-z]{0,4}\\{",
+        "\\}[a-z0-9 }]{1,4}",
+        "[a-z0-9 \t#={}\r\u{3000}-]{1,12}",
+    ]
+    .prop_filter("the item must be read back unchanged", |i| is_exact_item(i))
+}
+
+/// Generates [SlopValue]s that pass [Slop::validate]. About one in three
+/// lists is empty.
+pub fn value() -> impl Strategy<Value = SlopValue> {
+    prop_oneof![
+        2 => string().prop_map(SlopValue::String),
+        2 => vec(item(), 1..6).prop_map(SlopValue::List),
+        1 => Just(SlopValue::List(vec![])),
+    ]
+}
+
+/// Generates [Slop]s with up to 8 KVs that pass [Slop::validate].
+pub fn slop() -> impl Strategy<Value = Slop> {
+    hash_map(key(), value(), 0..8).prop_map(|kvs| {
+        let mut slop = Slop::new();
+
+        for (key, value) in kvs {
+            slop.insert_unchecked(key, value);
+        }
+
+        slop
+    })
+}
+
+impl Arbitrary for Slop {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Same as [slop()].
+    #[inline(always)]
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        slop().boxed()
+    }
+}
+
+impl Arbitrary for SlopValue {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    /// Same as [value()].
+    #[inline(always)]
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        value().boxed()
+    }
+}
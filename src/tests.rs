@@ -1,3 +1,569 @@
 //! This module defines the library's tests.
 
-//use super::*;
+#[cfg(feature = "async")]
+mod async_io {
+    use crate::{
+        Slop,
+        SlopReader,
+        error::{SlopError, SlopResult},
+    };
+
+    fn read(bytes: &[u8]) -> SlopResult<Slop> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(Slop::read_async(bytes))
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let err = read(b"a=1\nb=\xff").unwrap_err();
+
+        assert!(matches!(err, SlopError::InvalidEncoding { line: 1, offset: 6, .. }));
+        assert_eq!(err.to_string(), "(in line 2) the byte 6 is not valid utf-8");
+    }
+
+    #[test]
+    fn bom_and_utf16() {
+        let expected: Slop = "a=1\nb{\n    x\n}".parse().unwrap();
+
+        assert_eq!(read(b"\xef\xbb\xbfa=1\r\nb{\r\n    x\r\n}\r\n").unwrap(), expected);
+        assert_eq!(read(b"\xff\xfea\0=\x001\0\n\0b\0{\0\n\0x\0\n\0}\0").unwrap(), expected);
+        assert_eq!(read(b"\0a\0=\x001\0\n\0b\0{\0\n\0x\0\n\0}").unwrap(), expected);
+    }
+
+    #[test]
+    fn errors_match_the_sync_parser() {
+        for slop_str in ["a=1\nb{\nx", "a=1\n  not a kv", "#{\n}", "b{\nx\n}\nc\n"] {
+            let sync_err = slop_str.parse::<Slop>().unwrap_err();
+            let async_err = read(slop_str.as_bytes()).unwrap_err();
+
+            assert_eq!(async_err.to_string(), sync_err.to_string());
+        }
+    }
+
+    #[test]
+    fn next_kv_after_the_end() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let mut reader = SlopReader::new("a=1\n".as_bytes());
+
+        runtime.block_on(async {
+            assert!(reader.next_kv().await.unwrap().is_some());
+            assert_eq!(reader.next_kv().await.unwrap(), None);
+            assert_eq!(reader.next_kv().await.unwrap(), None);
+        });
+    }
+}
+
+#[cfg(any(feature = "toml", feature = "ini"))]
+mod convert {
+    use crate::{Slop, convert::Conversion};
+
+    // SLOP strings that are tricky to export.
+    const TRICKY: &[&str] = &[
+        "a=1\na.b=2",
+        "a.b.c=1\na.b=2",
+        "a..b=1\n.a=2\na.=3",
+        "a=1 # c\nb=x;y\nc:d=1\ne#f=1\n;g=1",
+        "x y=1\nx =1\na .b=1\na. b=1",
+        "[x=1\ns.[x=1\na].b=1\na]=1",
+        "a='q'\nb=\"q\" x\nc=\"\nd=x \"y\"",
+        "a=\\n\nb=\\\nc=\t\nd=  x\ne=\ne=é",
+        "one{\n    x\n}\nempty{\n}\nlist{\n    \"x\"\n    y\n}",
+    ];
+
+    // Checks that exporting and importing every tricky SLOP string, and every
+    // KV in them on its own, gives the same KVs back, unless the export reported
+    // a lossy value.
+    fn check_lossy_reports<F, I>(export: F, import: I)
+    where
+        F: Fn(&Slop) -> Conversion<String>,
+        I: Fn(&str) -> Option<Slop>,
+    {
+        for slop_str in TRICKY {
+            let slop: Slop = slop_str.parse().unwrap();
+            let single_kvs = slop.iter().map(|(k, v)| {
+                let mut single = Slop::new();
+                single.insert_unchecked(k.clone(), v.clone());
+                single
+            });
+
+            for slop in single_kvs.chain([slop.clone()]) {
+                let exported = export(&slop);
+
+                if exported.is_exact() {
+                    assert_eq!(import(&exported.output).as_ref(), Some(&slop), "{slop:?}");
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml() {
+        use crate::convert::toml;
+
+        check_lossy_reports(toml::to_string, |s| toml::from_str(s).ok().map(|c| c.output));
+
+        // TOML can represent every SLOP string.
+        for slop_str in TRICKY {
+            assert!(toml::to_string(&slop_str.parse().unwrap()).is_exact(), "{slop_str:?}");
+        }
+    }
+
+    #[cfg(feature = "ini")]
+    #[test]
+    fn ini() {
+        use alloc::vec::Vec;
+
+        use crate::convert::ini;
+
+        check_lossy_reports(ini::to_string, |s| ini::from_str(s).ok().map(|c| c.output));
+
+        let lossy_keys = |slop_str: &str| -> Vec<String> {
+            let conversion = ini::to_string(&slop_str.parse().unwrap());
+            conversion.lossy.into_iter().map(|l| l.key).collect()
+        };
+
+        assert_eq!(lossy_keys("a=1\na.b=2\nc=x;y#z"), Vec::<String>::new());
+        assert_eq!(lossy_keys("one{\n    x\n}\nempty{\n}\ntwo{\n    x\n    y\n}"), [
+            "empty",
+            "one",
+        ]);
+        assert_eq!(lossy_keys("a.=1\n.a=2\n[x=3\na].b=4"), [".a", "[x", "a.", "a].b"]);
+        assert_eq!(lossy_keys("x =1\na. b=2\na=\"q\"\nb=\tx"), ["a", "a. b", "b", "x "]);
+
+        let conversion = ini::to_string(&"[x=1\na=2".parse().unwrap());
+        assert_eq!(conversion.output, "a=2\n");
+        assert_eq!(
+            conversion.lossy[0].to_string(),
+            "(at `[x`) keys that start with `[` can't be represented, so it was dropped",
+        );
+    }
+}
+
+mod section {
+    use alloc::{string::String, vec::Vec};
+
+    use crate::{Slop, SlopTree, SlopValue, error::SlopError};
+
+    const TRICKY: &str = "a=1\na.=2\n.a=3\na..b=4\na.b=5\n.=6\nab.c=7\na.b.c=8";
+
+    fn keys<'a>(iter: impl Iterator<Item = (&'a str, &'a SlopValue)>) -> Vec<&'a str> {
+        let mut keys: Vec<_> = iter.map(|(k, _)| k).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn prefix_must_end_at_a_separator() {
+        let slop: Slop = TRICKY.parse().unwrap();
+        let a = slop.section("a");
+
+        // Neither `a` itself nor `ab.c` are in the section.
+        assert_eq!(keys(a.iter()), ["", ".b", "b", "b.c"]);
+        assert_eq!(a.child_sections().collect::<Vec<_>>(), ["", "b"]);
+        assert_eq!(a.get(""), Some(&"2".into()));
+        assert_eq!(slop.section("").child_sections().collect::<Vec<_>>(), ["", "a", "ab"]);
+    }
+
+    #[test]
+    fn long_separators() {
+        let slop: Slop = "a::b=1\na:::c=2\na::::d=3\nab::e=4".parse().unwrap();
+        let a = slop.section_with_separator("a", "::");
+
+        // The separator is matched right after the prefix.
+        assert_eq!(keys(a.iter()), ["::d", ":c", "b"]);
+        assert_eq!(a.child_sections().collect::<Vec<_>>(), [""]);
+        assert_eq!(a.section("").get("d"), Some(&"3".into()));
+        assert_eq!(slop.section_with_separator("", "::").to_tree().to_slop("::").unwrap(), slop);
+    }
+
+    #[test]
+    fn tree_round_trip() {
+        let slop: Slop = TRICKY.parse().unwrap();
+        let tree = slop.to_tree();
+
+        assert_eq!(tree.children[""].children["a"].value, Some("3".into()));
+        assert_eq!(tree.children[""].children[""].value, Some("6".into()));
+        assert_eq!(tree.children["a"].children[""].value, Some("2".into()));
+        assert_eq!(tree.to_slop(".").unwrap(), slop);
+        assert_eq!(Slop::try_from(tree).unwrap(), slop);
+    }
+
+    #[test]
+    fn tree_names_with_the_separator() {
+        let mut tree = SlopTree::new();
+        let a = tree.children.entry(String::from("a")).or_default();
+        a.children.entry(String::from("b.c")).or_default().value = Some("1".into());
+        let b = a.children.entry(String::from("b")).or_default();
+        b.children.entry(String::from("c")).or_default().value = Some("2".into());
+
+        // Both nodes would have the key `a.b.c`.
+        let err = tree.to_slop(".").unwrap_err();
+        assert!(matches!(err, SlopError::InvalidKey { key } if key == "a.b.c"));
+
+        let slop = tree.to_slop("/").unwrap();
+        assert_eq!(slop, "a/b.c=1\na/b/c=2".parse().unwrap());
+        assert_eq!(slop.section_with_separator("", "/").to_tree(), tree);
+    }
+
+    #[test]
+    fn inserted_keys_with_the_separator() {
+        let mut slop = Slop::new();
+        slop.section_mut("a").insert(String::from("b.c"), "1").unwrap();
+        slop.section_mut("a").section_mut("b").insert(String::from("d"), "2").unwrap();
+
+        assert_eq!(slop, "a.b.c=1\na.b.d=2".parse().unwrap());
+        assert_eq!(keys(slop.section("a.b").iter()), ["c", "d"]);
+
+        slop.section_mut("a.b").clear();
+        assert!(slop.is_empty());
+    }
+}
+
+#[cfg(feature = "std")]
+mod watch {
+    use std::{
+        fs::{self, File},
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use crate::{SlopDiff, SlopWatcher, error::ErrorKind};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("slop-watch-{name}-{}.slop", std::process::id()))
+    }
+
+    // Writes the file with a modification time of its own, so that changes
+    // are seen even if the file system's timestamps are coarse.
+    fn write(path: &PathBuf, contents: &str, version: u64) {
+        fs::write(path, contents).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + version);
+        File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn changes_are_detected() {
+        let path = temp_path("changes");
+        write(&path, "a=1\nb=2", 0);
+        let mut watcher = SlopWatcher::new(&path).unwrap();
+
+        // Nothing changed.
+        assert!(watcher.poll().unwrap().is_none());
+
+        // Same length, different modification time.
+        write(&path, "a=3\nb=2", 1);
+        let update = watcher.poll().unwrap().unwrap();
+        assert_eq!(update.diff, SlopDiff { changed: vec!["a".into()], ..Default::default() });
+        assert_eq!(watcher.snapshot().get("a"), Some(&"3".into()));
+
+        // Same modification time, different length.
+        write(&path, "a=3\nb=2\nc=4", 1);
+        let update = watcher.poll().unwrap().unwrap();
+        assert_eq!(update.diff.added, ["c"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn only_kv_changes_are_published() {
+        let path = temp_path("comments");
+        write(&path, "a=1", 0);
+        let mut watcher = SlopWatcher::new(&path).unwrap();
+        let updates = watcher.subscribe();
+
+        write(&path, "# a comment\n\na=1\n", 1);
+        assert!(watcher.poll().unwrap().is_none());
+        assert!(updates.try_recv().is_err());
+
+        write(&path, "b=1", 2);
+        assert!(watcher.poll().unwrap().is_some());
+        assert_eq!(updates.try_recv().unwrap().diff, SlopDiff {
+            added: vec!["b".into()],
+            removed: vec!["a".into()],
+            ..Default::default()
+        });
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn errors_keep_the_last_snapshot() {
+        let path = temp_path("errors");
+        write(&path, "a=1", 0);
+        let mut watcher = SlopWatcher::new(&path).unwrap();
+        let errors = Arc::new(Mutex::new(vec![]));
+        let sink = Arc::clone(&errors);
+        watcher.on_error(move |e| sink.lock().unwrap().push(e.kind()));
+
+        write(&path, "not a kv", 1);
+        assert_eq!(watcher.poll().unwrap_err().kind(), ErrorKind::InvalidLine);
+        // The invalid version isn't read again until it changes.
+        assert!(watcher.poll().unwrap().is_none());
+
+        fs::remove_file(&path).unwrap();
+        let err = watcher.poll().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert_eq!(err.path(), Some(path.as_path()));
+        assert_eq!(watcher.snapshot().get("a"), Some(&"1".into()));
+
+        // The diff is against the last good snapshot.
+        write(&path, "a=2", 2);
+        assert_eq!(watcher.poll().unwrap().unwrap().diff.changed, ["a"]);
+        assert_eq!(*errors.lock().unwrap(), [ErrorKind::InvalidLine, ErrorKind::Io]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn spawned_watcher() {
+        let path = temp_path("spawn");
+        write(&path, "a=1", 0);
+        let handle = SlopWatcher::new(&path).unwrap().spawn(Duration::from_millis(5));
+
+        write(&path, "a=2", 1);
+        let start = Instant::now();
+
+        while handle.snapshot().get("a") != Some(&"2".into()) {
+            assert!(start.elapsed() < Duration::from_secs(10), "the change wasn't seen");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        handle.stop();
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+mod format {
+    use alloc::string::String;
+
+    use crate::{FormatOptions, LineEnding, Slop, format};
+
+    const CRLF: FormatOptions = FormatOptions { sort_keys: false, line_ending: LineEnding::CrLf };
+    const SORTED: FormatOptions = FormatOptions { sort_keys: true, line_ending: LineEnding::Lf };
+
+    fn lf(slop_str: &str) -> String {
+        format(slop_str, &FormatOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn blank_input() {
+        assert_eq!(lf(""), "");
+        assert_eq!(lf("\n  \n\r\n"), "");
+        assert_eq!(lf("\n# only\n\n"), "# only\n");
+    }
+
+    #[test]
+    fn bom_and_crlf() {
+        assert_eq!(lf("\u{feff}a=1"), "a=1\n");
+        assert_eq!(lf("a=1\r\nb{\r\n  x\r\n}\r\n"), "a=1\nb{\n    x\n}\n");
+        assert_eq!(format("a=1\nb{\nx\n}", &CRLF).unwrap(), "a=1\r\nb{\r\n    x\r\n}\r\n");
+
+        // Only the last `\r` is part of the line break.
+        assert_eq!(lf("a=1\r\r\n"), "a=1\r\n");
+    }
+
+    #[test]
+    fn values_are_kept() {
+        let slop_str = "a=1 \t\n#  c  \nlist{\n#x\n   \n\n}\nempty{\n}";
+        let formatted = lf(slop_str);
+
+        assert_eq!(formatted, "a=1 \t\n#  c\nlist{\n    #x\n\n\n}\nempty{\n}\n");
+        assert_eq!(formatted.parse::<Slop>().unwrap(), slop_str.parse::<Slop>().unwrap());
+        assert_eq!(lf(&formatted), formatted);
+    }
+
+    #[test]
+    fn sort_keys() {
+        let slop_str = "# header\n\nb=1\n# about a\na=2\nb=3\n";
+        let sorted = format(slop_str, &SORTED).unwrap();
+
+        assert_eq!(sorted, "# header\n\n# about a\na=2\nb=1\nb=3\n");
+        // The last of the duplicate keys still wins.
+        assert_eq!(sorted.parse::<Slop>().unwrap(), slop_str.parse::<Slop>().unwrap());
+    }
+
+    #[test]
+    fn invalid_input() {
+        let err = format("a=1\nx{\n", &FormatOptions::default()).unwrap_err();
+        assert_eq!(err.line(), Some(1));
+    }
+}
+
+mod lint {
+    use alloc::vec::Vec;
+
+    use crate::{LintId, lint};
+
+    // Returns the ID, line and column of every lint.
+    fn lints(slop_str: &str) -> Vec<(LintId, usize, usize)> {
+        lint(slop_str).into_iter().map(|l| (l.id, l.line, l.column)).collect()
+    }
+
+    #[test]
+    fn bom_is_skipped() {
+        assert_eq!(lints("\u{feff}# comment\na=1"), []);
+        assert_eq!(lints("\u{feff}a=1 # c"), lints("a=1 # c"));
+        assert_eq!(lints("\u{feff}a=1\na=2"), [(LintId::DuplicateKey, 1, 0)]);
+
+        // Only at the start of the string.
+        assert_eq!(lints("a=1\n\u{feff}# comment"), [(LintId::InvalidLine, 1, 0)]);
+    }
+
+    #[test]
+    fn crlf() {
+        let slop_str = "a=1 # c\r\nlist{\r\n    #x\r\n}\r\na=2\r\n";
+
+        assert_eq!(lints(slop_str), lints(&slop_str.replace("\r\n", "\n")));
+        assert_eq!(lints(slop_str), [
+            (LintId::TrailingComment, 0, 4),
+            (LintId::CommentInList, 2, 4),
+            (LintId::DuplicateKey, 4, 0),
+        ]);
+
+        // Only the last `\r` is part of the line break.
+        assert_eq!(lints("a=1\r\r\nb=2"), [(LintId::NoRoundTrip, 0, 3)]);
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let slop_str = "a=1\na{\n}\n  a=3\nb=1\na =1\n# a=2\nlist{\n    a=4\n}";
+        let lint = lint(slop_str);
+
+        assert_eq!(lints(slop_str), [
+            (LintId::DuplicateKey, 1, 0),
+            (LintId::DuplicateKey, 3, 2),
+            (LintId::KeyWhitespace, 5, 1),
+        ]);
+        assert_eq!(
+            lint[0].message,
+            "the key `a` was already defined in line 1, and will be overridden",
+        );
+        assert_eq!(
+            lint[1].message,
+            "the key `a` was already defined in line 2, and will be overridden",
+        );
+    }
+}
+
+mod interpolate {
+    use alloc::{
+        format,
+        string::{String, ToString},
+    };
+
+    use crate::{
+        InterpolateOptions,
+        Slop,
+        error::{ErrorKind, SlopError},
+        parse::Limit,
+    };
+
+    fn max_len(max_len: Option<usize>) -> InterpolateOptions {
+        InterpolateOptions {
+            #[cfg(feature = "std")]
+            env: false,
+            max_len,
+        }
+    }
+
+    // Returns a SLOP string where `k0` expands to `2^depth` copies of `leaf`.
+    fn doubling(depth: usize, leaf: &str) -> String {
+        let mut slop_str: String = (0..depth)
+            .map(|i| format!("k{i}=${{k{}}}${{k{}}}\n", i + 1, i + 1))
+            .collect();
+        slop_str.push_str(&format!("k{depth}={leaf}"));
+        slop_str
+    }
+
+    #[test]
+    fn expanded_len_is_limited() {
+        let slop: Slop = doubling(64, "lol").parse().unwrap();
+        let err = slop.interpolated(&InterpolateOptions::default()).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+        assert!(matches!(
+            err,
+            SlopError::LimitExceeded { limit: Limit::ExpandedLen, line: None, max: 1048576 },
+        ));
+        assert_eq!(err.line(), None);
+        assert_eq!(err.to_string(), "a value is longer than 1048576 bytes once expanded");
+    }
+
+    #[test]
+    fn expanded_len_is_inclusive() {
+        let slop: Slop = doubling(3, "ab").parse().unwrap();
+
+        let expanded = slop.interpolated(&max_len(Some(16))).unwrap();
+        assert_eq!(expanded.get_string("k0"), Some(&"ab".repeat(8)));
+
+        assert!(slop.interpolated(&max_len(Some(15))).is_err());
+        assert!(slop.interpolated(&max_len(None)).is_ok());
+    }
+
+    #[test]
+    fn list_items_are_limited() {
+        let slop: Slop = "a=0123456789\nlist{\n    ${a}${a}\n}".parse().unwrap();
+        let err = slop.interpolated(&max_len(Some(15))).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::LimitExceeded);
+    }
+}
+
+#[cfg(feature = "schema")]
+mod schema {
+    use crate::{
+        Slop,
+        error::ErrorKind,
+        schema::{SlopSchema, ViolationKind},
+    };
+
+    fn port_schema() -> SlopSchema {
+        let schema: Slop = "port.min=1\nport.max=65535".parse().unwrap();
+        SlopSchema::from_slop(&schema).unwrap()
+    }
+
+    fn violations(schema: &SlopSchema, slop_str: &str) -> Vec<ViolationKind> {
+        let slop: Slop = slop_str.parse().unwrap();
+        schema.validate(&slop).err().unwrap_or_default().into_iter().map(|v| v.kind).collect()
+    }
+
+    #[test]
+    fn range() {
+        let schema = port_schema();
+
+        assert_eq!(violations(&schema, "port=1"), []);
+        assert_eq!(violations(&schema, "port= 65535 "), []);
+        assert_eq!(violations(&schema, "port=0"), [ViolationKind::OutOfRange("0".into())]);
+        assert_eq!(
+            violations(&schema, "port=65536"),
+            [ViolationKind::OutOfRange("65536".into())],
+        );
+        assert_eq!(
+            violations(&schema, "port=eighty"),
+            [ViolationKind::NotANumber("eighty".into())],
+        );
+    }
+
+    #[test]
+    fn non_finite_numbers_are_not_numbers() {
+        let schema = port_schema();
+
+        for value in ["NaN", "nan", "inf", "-inf", "infinity", "+Infinity"] {
+            assert_eq!(
+                violations(&schema, &format!("port={value}")),
+                [ViolationKind::NotANumber(value.into())],
+            );
+        }
+    }
+
+    #[test]
+    fn non_finite_bounds_are_rejected() {
+        for schema_str in ["port.min=NaN", "port.max=inf"] {
+            let schema: Slop = schema_str.parse().unwrap();
+            let err = SlopSchema::from_slop(&schema).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidSchema);
+        }
+    }
+}
@@ -3,7 +3,18 @@
 //! Eveything in this module is publically imported into [crate], so you can
 //! just import them from there.
 
-use std::{str::FromStr, fmt::Display};
+use alloc::{
+    borrow::ToOwned,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{str::FromStr, fmt::Display};
+
+use crate::{
+    error::{SlopError, SlopResult},
+    slop::{clean_up_line, is_exact_item, is_exact_string},
+};
 
 /// The possible values a [Slop]'s KVs can contain.
 /// 
@@ -33,13 +44,13 @@ impl SlopValue {
     /// Returns true if this [SlopValue] is a [SlopValue::String].
     #[inline(always)]
     pub fn is_string(&self) -> bool {
-        if let Self::String(_) = self { true } else { false }
+        matches!(self, Self::String(_))
     }
     
     /// Returns true if this [SlopValue] is a [SlopValue::List].
     #[inline(always)]
     pub fn is_list(&self) -> bool {
-        if let Self::List(_) = self { true } else { false }
+        matches!(self, Self::List(_))
     }
 
     /// Returns the contained string,
@@ -49,7 +60,7 @@ impl SlopValue {
     /// for [String].
     #[inline(always)]
     pub fn string(&self) -> Option<&String> {
-        if let Self::String(s) = self { Some(&s) } else { None }
+        if let Self::String(s) = self { Some(s) } else { None }
     }
 
     /// Returns the contained list,
@@ -59,7 +70,52 @@ impl SlopValue {
     /// for [Vec]<[String]>.
     #[inline(always)]
     pub fn list(&self) -> Option<&Vec<String>> {
-        if let Self::List(l) = self { Some(&l) } else { None }
+        if let Self::List(l) = self { Some(l) } else { None }
+    }
+
+    /// Returns `false` if writing the value to a SLOP string would break the
+    /// string's structure, that is, if a string or list item contains a
+    /// newline or a list item would be read back as the closing `}`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::SlopValue;
+    ///
+    /// assert!(SlopValue::from("a # b").is_valid());
+    /// assert!(!SlopValue::from("a\nb").is_valid());
+    /// assert!(!SlopValue::from(vec!["a", "  }"]).is_valid());
+    /// ```
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::String(s) => !s.contains('\n'),
+            Self::List(l) => l
+                .iter()
+                .all(|i| !i.contains('\n') && clean_up_line(i) != "}"),
+        }
+    }
+
+    /// Returns [SlopError::InvalidValue] if the value wouldn't be read back
+    /// unchanged from a SLOP string, which is stricter than
+    /// [SlopValue::is_valid]: a string can't end in `\r`, and a list item
+    /// can't start with whitespace either. (See [Slop::validate](crate::Slop::validate))
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::SlopValue;
+    ///
+    /// assert!(SlopValue::from(vec!["#item", "a=b", ""]).validate().is_ok());
+    /// assert!(SlopValue::from(vec!["  indented"]).validate().is_err());
+    /// assert!(SlopValue::from("ends in \r").validate().is_err());
+    /// ```
+    pub fn validate(&self) -> SlopResult<()> {
+        let exact = match self {
+            Self::String(s) => is_exact_string(s),
+            Self::List(l) => l.iter().all(|i| is_exact_item(i)),
+        };
+
+        if exact { Ok(()) } else { Err(SlopError::InvalidValue { value: self.to_string() }) }
     }
 
     /// If the value is a [SlopValue::String], attempts to parse it.
@@ -79,11 +135,7 @@ impl SlopValue {
     /// ```
     #[inline]
     pub fn parse_into<T>(&self) -> Option<Result<T, T::Err>> where T: FromStr {
-        if let Some(s) = self.string() {
-            Some(s.parse())
-        } else {
-            None
-        }
+        self.string().map(|s| s.parse())
     }
 
     /// Same as [SlopValue::to_string], but indents the values of
@@ -109,12 +161,23 @@ impl SlopValue {
     /// 
     /// assert_eq!(format!("{val}"), standard);
     /// assert_eq!(val.to_string_pretty(), pretty);
+    ///
+    /// // An empty list has no lines between its braces.
+    /// assert_eq!(SlopValue::List(vec![]).to_string_pretty(), "{\n}");
     /// ```
-    #[inline]
     pub fn to_string_pretty(&self) -> String {
         match self {
             Self::String(s) => format!("={s}"),
-            Self::List(l) => format!("{{\n    {}\n}}", l.join("\n    ")),
+            // Empty items aren't indented, so they don't leave trailing
+            // whitespace.
+            Self::List(l) => l.iter().fold(String::from("{\n"), |mut acc, i| {
+                if !i.is_empty() {
+                    acc.push_str("    ");
+                    acc.push_str(i);
+                }
+                acc.push('\n');
+                acc
+            }) + "}",
         }
     }
 }
@@ -122,10 +185,14 @@ impl SlopValue {
 impl Display for SlopValue {
     /// Displays the [SlopValue] as a the value part of a SLOP key-value.
     /// (including `=` and `{...}`)
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::String(s) => write!(f, "={s}"),
-            Self::List(l) => write!(f, "{{\n{}\n}}", l.join("\n")),
+            Self::List(l) => {
+                writeln!(f, "{{")?;
+                l.iter().try_for_each(|i| writeln!(f, "{i}"))?;
+                write!(f, "}}")
+            },
         }
     }
 }
@@ -168,6 +235,6 @@ impl From<&[String]> for SlopValue {
 impl From<&[&str]> for SlopValue {
     #[inline(always)]
     fn from(value: &[&str]) -> Self {
-        Self::List(value.into_iter().map(|s| s.to_string()).collect())
+        Self::List(value.iter().map(|s| s.to_string()).collect())
     }
 }
@@ -0,0 +1,197 @@
+//! JavaScript bindings, built with [wasm_bindgen]. Requires the `wasm`
+//! feature.
+//!
+//! Build the crate as a `cdylib` for `wasm32-unknown-unknown`, then generate
+//! the JavaScript glue with `wasm-bindgen`:
+//!
+//! ```sh
+//! cargo rustc --lib --release --features wasm --crate-type cdylib \
+//!     --target wasm32-unknown-unknown
+//! wasm-bindgen --target web --out-dir pkg \
+//!     target/wasm32-unknown-unknown/release/slop_rs.wasm
+//! ```
+//!
+//! The bindings use the same mapping as [json](crate::json): string KVs are
+//! strings, and list KVs are arrays of strings.
+//!
+//! ```js
+//! import { parse, stringify, lint, validate } from "./pkg/slop_rs.js";
+//!
+//! const mod = parse("name=example\ntags{\n    a\n    b\n}");
+//! mod.name; // "example"
+//! mod.tags; // ["a", "b"]
+//!
+//! stringify(mod); // "name=example\ntags{\n    a\n    b\n}\n"
+//!
+//! try {
+//!     parse("a=1\nnot a kv");
+//! } catch (e) {
+//!     e.name; // "SlopError"
+//!     e.kind; // "invalid-line"
+//!     e.line; // 2
+//! }
+//! ```
+//!
+//! Errors are thrown as JavaScript `Error`s whose `name` is `SlopError`, with
+//! two more properties: `kind`, which is the [ErrorKind](crate::error::ErrorKind)
+//! in kebab-case (such as `invalid-line` or `unclosed-list`), and `line`, the
+//! 1-based number of the line the error is in, or `undefined` if it isn't
+//! about a line. Every other line and column in the bindings is 1-based too.
+
+use js_sys::{Array, Error, JSON, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    FormatOptions,
+    Slop,
+    SlopDocument,
+    error::{SlopError, SlopResult},
+    lint::lint as lint_str,
+    schema::SlopSchema,
+};
+
+/// Parses a SLOP string into an object whose values are strings or arrays of
+/// strings. The keys are sorted.
+///
+/// Throws a `SlopError` if the string can't be parsed.
+#[wasm_bindgen]
+pub fn parse(slop_str: &str) -> Result<JsValue, JsValue> {
+    let json = parse_to_json(slop_str).map_err(to_js)?;
+
+    // The JSON of a `Slop` is always valid.
+    Ok(JSON::parse(&json).unwrap())
+}
+
+/// Converts an object whose values are strings or arrays of strings into a
+/// SLOP string in the canonical style, with the keys sorted. (See
+/// [Slop::to_string_formatted])
+///
+/// Throws a `SlopError` if the object has any other values, or if a key or
+/// value can't be written to a SLOP string.
+#[wasm_bindgen]
+pub fn stringify(object: JsValue) -> Result<String, JsValue> {
+    let json = JSON::stringify(&object)
+        .ok()
+        .and_then(|s| s.as_string())
+        .ok_or_else(|| error("unsupported-json", "the value can't be converted", None))?;
+
+    stringify_json(&json).map_err(to_js)
+}
+
+/// The part of [parse] that doesn't need JavaScript: parses a SLOP string into
+/// a JSON string, with the keys sorted.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::wasm::parse_to_json;
+///
+/// let json = parse_to_json("name=example\ntags{\n    a\n    b\n}").unwrap();
+/// assert_eq!(json, r#"{"name":"example","tags":["a","b"]}"#);
+///
+/// let err = parse_to_json("a=1\nnot a kv").unwrap_err();
+/// assert_eq!(err.line(), Some(1));
+/// ```
+pub fn parse_to_json(slop_str: &str) -> SlopResult<String> {
+    let slop: Slop = slop_str.parse()?;
+    Ok(slop.to_json().to_string())
+}
+
+/// The part of [stringify] that doesn't need JavaScript: converts a JSON
+/// string into a SLOP string in the canonical style, with the keys sorted.
+///
+/// ## Examples
+///
+/// ```
+/// use slop_rs::{error::ErrorKind, wasm::stringify_json};
+///
+/// let slop_str = stringify_json(r#"{"tags":["a","b"],"name":"example","empty":[]}"#).unwrap();
+/// assert_eq!(slop_str, "empty{\n}\nname=example\ntags{\n    a\n    b\n}\n");
+///
+/// let err = stringify_json(r#"{"count":1}"#).unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::UnsupportedJson);
+///
+/// let err = stringify_json(r#"{"a{":"1"}"#).unwrap_err();
+/// assert_eq!(err.kind(), ErrorKind::InvalidKey);
+/// ```
+pub fn stringify_json(json: &str) -> SlopResult<String> {
+    let json = serde_json::from_str(json)?;
+    let options = FormatOptions { sort_keys: true, ..Default::default() };
+
+    Ok(Slop::from_json(json)?.to_string_formatted(&options))
+}
+
+/// Lints a SLOP string. (See [lint](crate::lint::lint))
+///
+/// Returns an array of objects with the properties `id`, `severity`, `line`,
+/// `column` and `message`. Errors are returned as lints, not thrown.
+#[wasm_bindgen]
+pub fn lint(slop_str: &str) -> Array {
+    lint_str(slop_str)
+        .into_iter()
+        .map(|lint| JsValue::from(object(&[
+            ("id", lint.id.as_str().into()),
+            ("severity", lint.severity().to_string().into()),
+            ("line", (lint.line + 1).into()),
+            ("column", (lint.column + 1).into()),
+            ("message", lint.message.into()),
+        ])))
+        .collect()
+}
+
+/// Validates a SLOP string against a schema, written as a SLOP string too.
+/// (See [schema](crate::schema))
+///
+/// Returns an array of objects with the properties `key`, `line` (or
+/// `undefined` if the key is missing) and `message`. The array is empty if
+/// the string is valid.
+///
+/// Throws a `SlopError` if either string can't be parsed, or if the schema is
+/// invalid.
+#[wasm_bindgen]
+pub fn validate(slop_str: &str, schema_str: &str) -> Result<Array, JsValue> {
+    let doc: SlopDocument = slop_str.parse().map_err(to_js)?;
+    let schema: Slop = schema_str.parse().map_err(to_js)?;
+    let schema = SlopSchema::from_slop(&schema).map_err(to_js)?;
+
+    let violations = schema.validate_document(&doc).err().unwrap_or_default();
+
+    Ok(violations
+        .into_iter()
+        .map(|v| JsValue::from(object(&[
+            ("key", v.key.into()),
+            ("line", v.line.map_or(JsValue::UNDEFINED, |l| (l + 1).into())),
+            ("message", v.kind.to_string().into()),
+        ])))
+        .collect())
+}
+
+// Converts the error into a JavaScript `Error`. (See the module documentation)
+fn to_js(e: SlopError) -> JsValue {
+    error(e.kind().as_str(), &e.to_string(), e.line())
+}
+
+fn error(kind: &str, message: &str, line: Option<usize>) -> JsValue {
+    let error = Error::new(message);
+    error.set_name("SlopError");
+
+    let error = JsValue::from(error);
+    set(&error, "kind", kind.into());
+    set(&error, "line", line.map_or(JsValue::UNDEFINED, |l| (l + 1).into()));
+    error
+}
+
+fn object(properties: &[(&str, JsValue)]) -> Object {
+    let object = Object::new();
+
+    for (key, value) in properties {
+        set(&object, key, value.clone());
+    }
+
+    object
+}
+
+fn set(target: &JsValue, key: &str, value: JsValue) {
+    // Setting a property of a plain object or an error can't fail.
+    Reflect::set(target, &key.into(), &value).unwrap();
+}
@@ -0,0 +1,289 @@
+//! Defines the [SlopWatcher], which reloads a SLOP file when it changes.
+//!
+//! Eveything in this module is publically imported into [crate], so you can
+//! just import them from there.
+//!
+//! The watcher polls the file's metadata (its modification time and length),
+//! so it works the same on every platform, without a notification backend.
+//! Only the opened file is watched, not the files it includes.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        RwLock,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    Slop,
+    error::{SlopError, SlopResult},
+};
+
+type Callback<T> = Box<dyn FnMut(&T) + Send>;
+
+/// Watches a SLOP file and publishes a new snapshot of it whenever its KVs
+/// change.
+///
+/// Call [SlopWatcher::poll] to check the file, or [SlopWatcher::spawn] to
+/// check it periodically on a background thread. If the file can't be read or
+/// parsed, the last good snapshot is kept, and the error is passed to the
+/// [SlopWatcher::on_error] callbacks.
+///
+/// ## Examples
+///
+/// ```
+/// use std::fs;
+///
+/// use slop_rs::SlopWatcher;
+///
+/// let path = std::env::temp_dir().join(format!("slop-watcher-{}.slop", std::process::id()));
+/// fs::write(&path, "port=8080\nhost=localhost").unwrap();
+///
+/// let mut watcher = SlopWatcher::new(&path).unwrap();
+/// let updates = watcher.subscribe();
+///
+/// fs::write(&path, "port=9090\nhost=localhost\nname=main").unwrap();
+/// watcher.poll().unwrap();
+///
+/// let update = updates.try_recv().unwrap();
+/// assert_eq!(update.slop.get("port"), Some(&"9090".into()));
+/// assert_eq!(update.diff.changed, ["port"]);
+/// assert_eq!(update.diff.added, ["name"]);
+///
+/// // The last good snapshot is kept if the file becomes invalid.
+/// fs::write(&path, "not a kv").unwrap();
+/// assert!(watcher.poll().is_err());
+/// assert_eq!(watcher.snapshot().get("port"), Some(&"9090".into()));
+/// # fs::remove_file(&path).unwrap();
+/// ```
+pub struct SlopWatcher {
+    path: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+    current: Arc<RwLock<Arc<Slop>>>,
+    on_change: Vec<Callback<SlopUpdate>>,
+    on_error: Vec<Callback<SlopError>>,
+}
+
+impl SlopWatcher {
+    /// Opens the file at the provided path with [Slop::open], and starts
+    /// watching it.
+    ///
+    /// Returns a [SlopError] if the file can't be opened, since there's no
+    /// good snapshot to fall back on yet.
+    pub fn new<P: AsRef<Path>>(path: P) -> SlopResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let stamp = stamp(&path).ok();
+        let slop = Slop::open(&path)?;
+
+        Ok(Self {
+            path,
+            stamp,
+            current: Arc::new(RwLock::new(Arc::new(slop))),
+            on_change: vec![],
+            on_error: vec![],
+        })
+    }
+
+    /// Returns the path of the watched file.
+    #[inline(always)]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the last good snapshot of the file.
+    #[inline(always)]
+    pub fn snapshot(&self) -> Arc<Slop> {
+        snapshot(&self.current)
+    }
+
+    /// Calls `callback` with every update.
+    pub fn on_change<F: FnMut(&SlopUpdate) + Send + 'static>(&mut self, callback: F) {
+        self.on_change.push(Box::new(callback));
+    }
+
+    /// Calls `callback` with every error found while reloading the file.
+    pub fn on_error<F: FnMut(&SlopError) + Send + 'static>(&mut self, callback: F) {
+        self.on_error.push(Box::new(callback));
+    }
+
+    /// Returns a channel that receives every update.
+    ///
+    /// The channel is dropped when the receiver is.
+    pub fn subscribe(&mut self) -> Receiver<SlopUpdate> {
+        let (sender, receiver) = mpsc::channel();
+
+        // A failed send means that the receiver was dropped, so there's
+        // nothing to do.
+        self.on_change(move |update| { let _ = sender.send(update.clone()); });
+        receiver
+    }
+
+    /// Reloads the file if its metadata changed since the last check.
+    ///
+    /// Returns the update that was published, or [None] if the file didn't
+    /// change or its KVs are the same. If the file can't be read or parsed,
+    /// the error is returned (and passed to the [SlopWatcher::on_error]
+    /// callbacks), and the file won't be reloaded until it changes again.
+    pub fn poll(&mut self) -> SlopResult<Option<SlopUpdate>> {
+        match self.reload() {
+            Ok(update) => Ok(update),
+            Err(e) => {
+                self.on_error.iter_mut().for_each(|callback| callback(&e));
+                Err(e)
+            },
+        }
+    }
+
+    /// Moves the watcher to a background thread, which polls the file every
+    /// `interval`.
+    ///
+    /// The thread stops when the returned [WatcherHandle] is dropped.
+    pub fn spawn(mut self, interval: Duration) -> WatcherHandle {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let current = Arc::clone(&self.current);
+
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                // Errors are passed to the callbacks.
+                let _ = self.poll();
+            }
+        });
+
+        WatcherHandle { current, stop: Some(stop), thread: Some(thread) }
+    }
+
+    fn reload(&mut self) -> SlopResult<Option<SlopUpdate>> {
+        let new_stamp = stamp(&self.path)?;
+
+        if self.stamp == Some(new_stamp) {
+            return Ok(None);
+        }
+
+        self.stamp = Some(new_stamp);
+
+        let slop = Arc::new(Slop::open(&self.path)?);
+        let diff = SlopDiff::between(&self.snapshot(), &slop);
+
+        if diff.is_empty() {
+            return Ok(None);
+        }
+
+        // A poisoned lock still holds a valid snapshot.
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::clone(&slop);
+
+        let update = SlopUpdate { slop, diff };
+        self.on_change.iter_mut().for_each(|callback| callback(&update));
+        Ok(Some(update))
+    }
+}
+
+/// A handle to a [SlopWatcher] running on a background thread. (See
+/// [SlopWatcher::spawn])
+///
+/// Dropping the handle stops the thread.
+pub struct WatcherHandle {
+    current: Arc<RwLock<Arc<Slop>>>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatcherHandle {
+    /// Returns the last good snapshot of the file.
+    #[inline(always)]
+    pub fn snapshot(&self) -> Arc<Slop> {
+        snapshot(&self.current)
+    }
+
+    /// Stops the thread and waits for it to finish.
+    #[inline(always)]
+    pub fn stop(self) {}
+}
+
+impl Drop for WatcherHandle {
+    fn drop(&mut self) {
+        // Dropping the sender wakes the thread up.
+        self.stop.take();
+
+        if let Some(thread) = self.thread.take() {
+            // A panic in a callback already printed its message.
+            let _ = thread.join();
+        }
+    }
+}
+
+/// A new snapshot of a watched file, published by a [SlopWatcher].
+#[derive(Debug, Clone)]
+pub struct SlopUpdate {
+    /// The KVs of the file.
+    pub slop: Arc<Slop>,
+    /// The keys that changed since the previous snapshot.
+    pub diff: SlopDiff,
+}
+
+/// The keys that differ between two [Slop]s. Each list is sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlopDiff {
+    /// The keys that are only in the new [Slop].
+    pub added: Vec<String>,
+    /// The keys that are only in the old [Slop].
+    pub removed: Vec<String>,
+    /// The keys that are in both, but with different values.
+    pub changed: Vec<String>,
+}
+
+impl SlopDiff {
+    /// Compares two [Slop]s.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use slop_rs::{Slop, SlopDiff};
+    ///
+    /// let old: Slop = "a=1\nb=2\nc=3".parse().unwrap();
+    /// let new: Slop = "a=1\nb=20\nd=4".parse().unwrap();
+    /// let diff = SlopDiff::between(&old, &new);
+    ///
+    /// assert_eq!(diff.added, ["d"]);
+    /// assert_eq!(diff.removed, ["c"]);
+    /// assert_eq!(diff.changed, ["b"]);
+    /// ```
+    pub fn between(old: &Slop, new: &Slop) -> Self {
+        let keys: BTreeSet<&String> = old.iter().chain(new.iter()).map(|(k, _)| k).collect();
+        let mut diff = Self::default();
+
+        for key in keys {
+            match (old.get(key), new.get(key)) {
+                (None, Some(_)) => diff.added.push(key.clone()),
+                (Some(_), None) => diff.removed.push(key.clone()),
+                (Some(a), Some(b)) if a != b => diff.changed.push(key.clone()),
+                _ => {},
+            }
+        }
+
+        diff
+    }
+
+    /// Returns `true` if no keys differ.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// Returns what identifies a version of the file: its modification time and
+// length.
+fn stamp(path: &Path) -> SlopResult<(SystemTime, u64)> {
+    let with_path = |e| SlopError::from(e).with_path(path);
+    let metadata = fs::metadata(path).map_err(with_path)?;
+    Ok((metadata.modified().map_err(with_path)?, metadata.len()))
+}
+
+fn snapshot(current: &RwLock<Arc<Slop>>) -> Arc<Slop> {
+    // A poisoned lock still holds a valid snapshot.
+    Arc::clone(&current.read().unwrap_or_else(|e| e.into_inner()))
+}
@@ -0,0 +1,132 @@
+//! Calls the C API the way a C program would.
+
+use std::{
+    ffi::{CStr, CString, c_char},
+    ptr,
+};
+
+use slop_rs::capi::*;
+
+fn c(s: &str) -> CString {
+    CString::new(s).unwrap()
+}
+
+// Takes ownership of a string returned by the API.
+unsafe fn take(s: *mut c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+
+    let string = CStr::from_ptr(s).to_str().unwrap().to_string();
+    slop_string_free(s);
+    Some(string)
+}
+
+unsafe fn message(error: &SlopCError) -> &str {
+    CStr::from_ptr(error.message).to_str().unwrap()
+}
+
+#[test]
+fn parse_and_read() {
+    let src = "name=example\ntags{\n    a\n    b\n}\n";
+
+    unsafe {
+        let slop = slop_parse(src.as_ptr().cast(), src.len(), ptr::null_mut());
+        assert!(!slop.is_null());
+
+        assert_eq!(take(slop_get_string(slop, c("name").as_ptr())), Some("example".into()));
+        assert_eq!(take(slop_get_string(slop, c("tags").as_ptr())), None);
+        assert_eq!(take(slop_get_string(slop, c("missing").as_ptr())), None);
+
+        let mut len = 0;
+        assert!(slop_list_len(slop, c("tags").as_ptr(), &mut len));
+        assert_eq!(len, 2);
+        assert!(!slop_list_len(slop, c("name").as_ptr(), &mut len));
+
+        assert_eq!(take(slop_list_item(slop, c("tags").as_ptr(), 1)), Some("b".into()));
+        assert_eq!(take(slop_list_item(slop, c("tags").as_ptr(), 2)), None);
+
+        slop_free(slop);
+    }
+}
+
+#[test]
+fn edit_and_save() {
+    let path = std::env::temp_dir().join(format!("slop-capi-{}.slop", std::process::id()));
+    let path = c(path.to_str().unwrap());
+    let items = [c("x"), c("y")];
+    let items: Vec<_> = items.iter().map(|i| i.as_ptr()).collect();
+    let mut error = SlopCError { message: ptr::null_mut(), line: 0 };
+
+    unsafe {
+        let slop = slop_new();
+        assert!(slop_insert_string(slop, c("a").as_ptr(), c("1").as_ptr(), &mut error));
+        assert!(slop_insert_string(slop, c("b").as_ptr(), c("2").as_ptr(), &mut error));
+        assert!(slop_insert_list(slop, c("list").as_ptr(), items.as_ptr(), 2, &mut error));
+        assert!(slop_remove(slop, c("b").as_ptr()));
+        assert!(!slop_remove(slop, c("b").as_ptr()));
+
+        assert!(!slop_insert_string(slop, c("a=b").as_ptr(), c("1").as_ptr(), &mut error));
+        assert_eq!(message(&error), "the key `a=b` contains invalid characters");
+
+        // A saved file would read these back as a comment and as `x`.
+        assert!(!slop_insert_string(slop, c("#hidden").as_ptr(), c("1").as_ptr(), &mut error));
+        let padded = [c("  x")];
+        let padded: Vec<_> = padded.iter().map(|i| i.as_ptr()).collect();
+        assert!(!slop_insert_list(slop, c("list").as_ptr(), padded.as_ptr(), 1, &mut error));
+        assert!(message(&error).starts_with("the value"));
+
+        assert!(slop_save(slop, path.as_ptr(), true, &mut error));
+        slop_free(slop);
+
+        let slop = slop_open(path.as_ptr(), &mut error);
+        assert!(!slop.is_null());
+        assert_eq!(take(slop_get_string(slop, c("a").as_ptr())), Some("1".into()));
+        assert_eq!(take(slop_list_item(slop, c("list").as_ptr(), 0)), Some("x".into()));
+        slop_free(slop);
+
+        slop_error_free(&mut error);
+        assert!(error.message.is_null());
+    }
+
+    std::fs::remove_file(path.to_str().unwrap()).unwrap();
+}
+
+#[test]
+fn errors() {
+    let mut error = SlopCError { message: ptr::null_mut(), line: 0 };
+
+    unsafe {
+        let src = "a=1\nlist{\nb";
+        assert!(slop_parse(src.as_ptr().cast(), src.len(), &mut error).is_null());
+        assert_eq!(message(&error), "(in line 2) `list{` is not closed");
+        assert_eq!(error.line, 2);
+
+        let src = b"a=1\nb=\xff";
+        assert!(slop_parse(src.as_ptr().cast(), src.len(), &mut error).is_null());
+        assert_eq!(message(&error), "(in line 2) the byte 6 is not valid utf-8");
+        assert_eq!(error.line, 2);
+
+        assert!(slop_open(c("/nonexistent/file.slop").as_ptr(), &mut error).is_null());
+        assert_eq!(error.line, 0);
+
+        slop_error_free(&mut error);
+    }
+}
+
+// The build script generates the header into `OUT_DIR`. Run the test with
+// `UPDATE_HEADER=1` to copy it over the committed one.
+#[test]
+fn header_is_up_to_date() {
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/slop.h"));
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/include/slop.h");
+
+    if std::env::var_os("UPDATE_HEADER").is_some() {
+        std::fs::write(path, generated).unwrap();
+    }
+
+    assert!(
+        std::fs::read_to_string(path).unwrap() == generated,
+        "`include/slop.h` is out of date, run the test with `UPDATE_HEADER=1` to update it",
+    );
+}
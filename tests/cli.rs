@@ -0,0 +1,232 @@
+//! Runs the `slop` binary and checks its output and exit codes.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+struct Output {
+    code: i32,
+    stdout: String,
+    stderr: String,
+}
+
+// A directory of its own for each test, since they run in parallel.
+struct Dir(PathBuf);
+
+impl Dir {
+    fn new(name: &str) -> Dir {
+        let path = std::env::temp_dir().join(format!("slop-cli-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Dir(path)
+    }
+
+    fn file(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.0.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn slop(&self, args: &[&str]) -> Output {
+        let output = Command::new(env!("CARGO_BIN_EXE_slop"))
+            .args(args)
+            .current_dir(&self.0)
+            .output()
+            .unwrap();
+
+        Output {
+            code: output.status.code().unwrap(),
+            stdout: String::from_utf8(output.stdout).unwrap(),
+            stderr: String::from_utf8(output.stderr).unwrap(),
+        }
+    }
+}
+
+impl Drop for Dir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn get() {
+    let dir = Dir::new("get");
+    dir.file("config.slop", "# comment\nname=example\ntags{\n    a\n    b\n}\n");
+
+    let output = dir.slop(&["get", "config.slop", "name"]);
+    assert_eq!((output.code, output.stdout.as_str(), output.stderr.as_str()), (0, "example\n", ""));
+
+    let output = dir.slop(&["get", "config.slop", "tags"]);
+    assert_eq!((output.code, output.stdout.as_str()), (0, "a\nb\n"));
+
+    let output = dir.slop(&["get", "config.slop", "missing"]);
+    assert_eq!(output.code, 1);
+    assert_eq!(output.stdout, "");
+    assert_eq!(output.stderr, "slop: the key `missing` does not exist\n");
+}
+
+#[test]
+fn get_from_utf16() {
+    let dir = Dir::new("utf16");
+    fs::write(dir.0.join("config.slop"), b"\xff\xfea\0=\x001\0").unwrap();
+
+    let output = dir.slop(&["get", "config.slop", "a"]);
+    assert_eq!((output.code, output.stdout.as_str()), (0, "1\n"));
+}
+
+#[test]
+fn invalid_files() {
+    let dir = Dir::new("invalid");
+    dir.file("line.slop", "a=1\n  bad\n");
+    fs::write(dir.0.join("utf8.slop"), b"a=1\nb=\xff").unwrap();
+
+    let output = dir.slop(&["get", "line.slop", "a"]);
+    assert_eq!(output.code, 3);
+    assert_eq!(output.stdout, "");
+    assert!(output.stderr.starts_with("error: `bad` is not a valid kv\n --> line.slop:2:3\n"));
+
+    let output = dir.slop(&["keys", "utf8.slop"]);
+    assert_eq!(output.code, 3);
+    assert!(output.stderr.contains("the byte 6 is not valid utf-8"), "{}", output.stderr);
+
+    let output = dir.slop(&["get", "missing.slop", "a"]);
+    assert_eq!(output.code, 4);
+    assert!(output.stderr.starts_with("slop: (in `missing.slop`) io error: "));
+}
+
+#[test]
+fn set() {
+    let dir = Dir::new("set");
+    let path = dir.file("config.slop", "# comment\na=1\n");
+
+    let output = dir.slop(&["set", "config.slop", "b", "2"]);
+    assert_eq!((output.code, output.stdout.as_str(), output.stderr.as_str()), (0, "", ""));
+    assert_eq!(read(&path), "# comment\na=1\nb=2\n");
+
+    let output = dir.slop(&["set-list", "config.slop", "a", "x", "y"]);
+    assert_eq!(output.code, 0);
+    assert_eq!(read(&path), "# comment\na{\n    x\n    y\n}\nb=2\n");
+
+    let output = dir.slop(&["set", "new.slop", "a", "1"]);
+    assert_eq!(output.code, 0);
+    assert_eq!(read(&dir.0.join("new.slop")), "a=1\n");
+}
+
+#[test]
+fn set_invalid_args() {
+    let dir = Dir::new("set-invalid");
+    let path = dir.file("config.slop", "a=1\n");
+
+    for args in [["a=b", "1"], ["#a", "1"], [" a", "1"], ["a", "1\n2"]] {
+        let output = dir.slop(&["set", "config.slop", args[0], args[1]]);
+        assert_eq!(output.code, 2, "{args:?}");
+        assert!(output.stderr.starts_with("slop: "), "{args:?}");
+    }
+
+    let output = dir.slop(&["set-list", "config.slop", "a", "  indented"]);
+    assert_eq!(output.code, 2);
+    assert_eq!(read(&path), "a=1\n");
+
+    let output = dir.slop(&["set", "config.slop", "a"]);
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.starts_with("error: "));
+}
+
+#[test]
+fn keys() {
+    let dir = Dir::new("keys");
+    dir.file("config.slop", "b=1\n# a=1\na{\n}\nc=3\n");
+
+    let output = dir.slop(&["keys", "config.slop"]);
+    assert_eq!((output.code, output.stdout.as_str(), output.stderr.as_str()), (0, "b\na\nc\n", ""));
+}
+
+#[test]
+fn fmt_check() {
+    let dir = Dir::new("fmt");
+    dir.file("formatted.slop", "# comment\na=1\n");
+    let path = dir.file("unformatted.slop", "  a =1\nb{\nx\n}");
+
+    let output = dir.slop(&["fmt", "--check", "formatted.slop"]);
+    assert_eq!((output.code, output.stdout.as_str(), output.stderr.as_str()), (0, "", ""));
+
+    let output = dir.slop(&["fmt", "--check", "formatted.slop", "unformatted.slop"]);
+    assert_eq!(output.code, 1);
+    assert_eq!(output.stdout, "");
+    assert_eq!(output.stderr, "slop: `unformatted.slop` is not formatted\n");
+    assert_eq!(read(&path), "  a =1\nb{\nx\n}");
+
+    let output = dir.slop(&["fmt", "unformatted.slop"]);
+    assert_eq!(output.code, 0);
+    assert_eq!(dir.slop(&["fmt", "--check", "unformatted.slop"]).code, 0);
+
+    assert_eq!(dir.slop(&["fmt", "--check"]).code, 2);
+}
+
+#[test]
+fn lint() {
+    let dir = Dir::new("lint");
+    dir.file("clean.slop", "a=1\n");
+    dir.file("warnings.slop", "a=1 # c\nb=2\nb=3\n");
+    dir.file("errors.slop", "a=1\nnot a kv\n");
+
+    let output = dir.slop(&["lint", "clean.slop"]);
+    assert_eq!((output.code, output.stdout.as_str(), output.stderr.as_str()), (0, "", ""));
+
+    let output = dir.slop(&["lint", "clean.slop", "warnings.slop"]);
+    assert_eq!(output.code, 1);
+    assert_eq!(
+        output.stdout,
+        "warnings.slop:1:5: warning[trailing-comment]: `#` is part of the value, not the start \
+        of a comment\nwarnings.slop:3:1: warning[duplicate-key]: the key `b` was already \
+        defined in line 2, and will be overridden\n",
+    );
+
+    let output = dir.slop(&["lint", "warnings.slop", "errors.slop"]);
+    assert_eq!(output.code, 3);
+    assert!(output.stdout.contains("errors.slop:2:1: error[invalid-line]: "), "{}", output.stdout);
+
+    assert_eq!(dir.slop(&["lint"]).code, 2);
+}
+
+#[test]
+fn convert() {
+    let dir = Dir::new("convert");
+    dir.file("config.slop", "a=1\nb{\n    x\n}\n");
+    dir.file("config.json", r#"{"b": ["x"], "a": "1"}"#);
+    dir.file("number.json", r#"{"a": 1}"#);
+    dir.file("key.json", r##"{"#a": "1"}"##);
+
+    let output = dir.slop(&["convert", "--to", "json", "config.slop"]);
+    assert_eq!(output.code, 0);
+    assert_eq!(output.stdout, "{\n  \"a\": \"1\",\n  \"b\": [\n    \"x\"\n  ]\n}\n");
+
+    let output = dir.slop(&["convert", "--from", "json", "config.json"]);
+    assert_eq!((output.code, output.stderr.as_str()), (0, ""));
+    assert_eq!(output.stdout, "a=1\nb{\n    x\n}\n");
+
+    let output = dir.slop(&["convert", "--from", "json", "config.json", "-o", "out.slop"]);
+    assert_eq!((output.code, output.stdout.as_str()), (0, ""));
+    assert_eq!(read(&dir.0.join("out.slop")), "a=1\nb{\n    x\n}\n");
+
+    let output = dir.slop(&["convert", "--from", "json", "number.json"]);
+    assert_eq!(output.code, 3);
+    assert_eq!(
+        output.stderr,
+        "slop: (in `number.json`) (at `a`) found a number, but only strings and arrays of \
+        strings are supported\n",
+    );
+
+    let output = dir.slop(&["convert", "--from", "json", "key.json"]);
+    assert_eq!(output.code, 3);
+    assert_eq!(output.stderr, "slop: (in `key.json`) the key `#a` contains invalid characters\n");
+
+    assert_eq!(dir.slop(&["convert", "config.slop"]).code, 2);
+    assert_eq!(dir.slop(&["convert", "--to", "json", "--from", "json", "config.slop"]).code, 2);
+}
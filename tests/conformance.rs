@@ -0,0 +1,37 @@
+//! Runs the conformance corpus against this crate.
+
+use slop_rs::{
+    Slop,
+    SlopDocument,
+    conformance::{self, Outcome},
+};
+
+#[test]
+fn slop() {
+    let cases = conformance::load(conformance::corpus_dir()).unwrap();
+    let failures = conformance::run(&cases, |input| Outcome::of(Slop::from_bytes(input)));
+
+    assert!(!cases.is_empty());
+    assert!(failures.is_empty(), "{failures:#?}");
+}
+
+#[test]
+fn document() {
+    let cases = conformance::load(conformance::corpus_dir()).unwrap();
+    let failures = conformance::run(&cases, |input| {
+        let doc = slop_rs::decode(input, false).and_then(|s| s.parse::<SlopDocument>());
+        Outcome::of(doc.map(|doc| doc.to_slop()))
+    });
+
+    assert!(failures.is_empty(), "{failures:#?}");
+}
+
+#[test]
+fn parse_with() {
+    let cases = conformance::load(conformance::corpus_dir()).unwrap();
+    let failures = conformance::run(&cases, |input| {
+        Outcome::of(Slop::read_with(input, &Default::default()))
+    });
+
+    assert!(failures.is_empty(), "{failures:#?}");
+}
@@ -0,0 +1,248 @@
+//! Talks to the `slop-lsp` binary over its standard input and output.
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::{Value, json};
+
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Client {
+    fn start(initialization_options: Value) -> Client {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_slop-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Client { child, stdin, stdout, next_id: 0 };
+
+        client.request("initialize", json!({
+            "capabilities": {},
+            "initializationOptions": initialization_options,
+        }));
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            let header = header.trim_end();
+
+            if header.is_empty() {
+                break;
+            }
+            if let Some(l) = header.strip_prefix("Content-Length: ") {
+                length = l.parse().unwrap();
+            }
+        }
+
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        let mut message = json!({ "jsonrpc": "2.0", "id": id, "method": method });
+        with_params(&mut message, params);
+        self.send(message);
+
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        let mut message = json!({ "jsonrpc": "2.0", "method": method });
+        with_params(&mut message, params);
+        self.send(message);
+    }
+
+    // Waits for the next notification with the given method.
+    fn notification(&mut self, method: &str) -> Value {
+        loop {
+            let message = self.receive();
+            if message["method"] == method {
+                return message["params"].clone();
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) -> Value {
+        self.notify("textDocument/didOpen", json!({
+            "textDocument": { "uri": uri, "languageId": "slop", "version": 0, "text": text },
+        }));
+        self.notification("textDocument/publishDiagnostics")
+    }
+
+    fn shutdown(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+// The server rejects `null` parameters, so they are left out instead.
+fn with_params(message: &mut Value, params: Value) {
+    if !params.is_null() {
+        message["params"] = params;
+    }
+}
+
+const URI: &str = "file:///tmp/test.slop";
+
+fn at(line: u32, character: u32) -> Value {
+    json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+}
+
+#[test]
+fn diagnostics() {
+    let mut client = Client::start(Value::Null);
+
+    let params = client.open(URI, "key=value\nnot a kv\nlist{\na");
+    let codes: Vec<_> = params["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| (d["code"].as_str().unwrap(), d["range"]["start"]["line"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(codes, [("invalid-line", 1), ("unclosed-list", 2)]);
+
+    client.notify("textDocument/didChange", json!({
+        "textDocument": { "uri": URI, "version": 1 },
+        "contentChanges": [{ "text": "key=value\n" }],
+    }));
+    let params = client.notification("textDocument/publishDiagnostics");
+    assert_eq!(params["diagnostics"], json!([]));
+
+    // Saved, this starts like UTF-16, which `Slop::open` fails to decode.
+    client.notify("textDocument/didChange", json!({
+        "textDocument": { "uri": URI, "version": 2 },
+        "contentChanges": [{ "text": "a\0=" }],
+    }));
+    let params = client.notification("textDocument/publishDiagnostics");
+    assert_eq!(params["diagnostics"][0]["code"], "invalid-encoding");
+    assert_eq!(params["diagnostics"][0]["range"]["start"]["line"], 0);
+
+    client.shutdown();
+}
+
+#[test]
+fn outline_folding_and_formatting() {
+    let mut client = Client::start(Value::Null);
+    client.open(URI, "b=1\n  list{\na\n}\n");
+    let document = json!({ "textDocument": { "uri": URI } });
+
+    let symbols = client.request("textDocument/documentSymbol", document.clone());
+    let names: Vec<_> = symbols.as_array().unwrap().iter().map(|s| s["name"].clone()).collect();
+    assert_eq!(names, [json!("b"), json!("list")]);
+    assert_eq!(symbols[1]["selectionRange"]["start"], json!({ "line": 1, "character": 2 }));
+
+    let ranges = client.request("textDocument/foldingRange", document.clone());
+    assert_eq!(ranges[0]["startLine"], 1);
+    assert_eq!(ranges[0]["endLine"], 3);
+
+    let mut formatting = document;
+    formatting["options"] = json!({ "tabSize": 4, "insertSpaces": true });
+    let edits = client.request("textDocument/formatting", formatting);
+    assert_eq!(edits[0]["newText"], "b=1\nlist{\n    a\n}\n");
+
+    client.shutdown();
+}
+
+#[test]
+fn definition() {
+    let mut client = Client::start(Value::Null);
+    client.open(URI, "key=a\nother=b\nkey=c\n");
+
+    let location = client.request("textDocument/definition", at(0, 1));
+    assert_eq!(location["uri"], URI);
+    assert_eq!(location["range"]["start"]["line"], 2);
+
+    client.shutdown();
+}
+
+#[test]
+fn definition_in_included_file() {
+    let dir = std::env::temp_dir().join(format!("slop-lsp-include-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let dir = dir.canonicalize().unwrap();
+    std::fs::write(dir.join("base.slop"), "other=x\nkey=b\n").unwrap();
+
+    let main_text = "key=a\n#!include base.slop\n";
+    std::fs::write(dir.join("main.slop"), main_text).unwrap();
+
+    let main_uri = format!("file://{}", dir.join("main.slop").display());
+    let base_uri = format!("file://{}", dir.join("base.slop").display());
+
+    let mut client = Client::start(Value::Null);
+    client.open(&main_uri, main_text);
+    let position = |line, character| json!({
+        "textDocument": { "uri": main_uri },
+        "position": { "line": line, "character": character },
+    });
+
+    let location = client.request("textDocument/definition", position(0, 1));
+    assert_eq!(location["uri"], base_uri);
+    assert_eq!(location["range"]["start"]["line"], 1);
+
+    let location = client.request("textDocument/definition", position(1, 3));
+    assert_eq!(location["uri"], base_uri);
+    assert_eq!(location["range"]["start"]["line"], 0);
+
+    client.shutdown();
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn schema() {
+    let dir = std::env::temp_dir().join(format!("slop-lsp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let schema_path = dir.join("schema.slop");
+    std::fs::write(
+        &schema_path,
+        "mode.required=true\nmode.enum{\ndev\nprod\n}\nmode.description=The mode.\nport.kind=string",
+    ).unwrap();
+
+    let mut client = Client::start(json!({ "schema": schema_path }));
+
+    let params = client.open(URI, "mode=test\n");
+    assert_eq!(params["diagnostics"][0]["code"], "schema");
+
+    let hover = client.request("textDocument/hover", at(0, 2));
+    assert!(hover["contents"]["value"].as_str().unwrap().contains("The mode."));
+
+    let items = client.request("textDocument/completion", at(0, 5));
+    let labels: Vec<_> = items.as_array().unwrap().iter().map(|i| i["label"].clone()).collect();
+    assert_eq!(labels, [json!("dev"), json!("prod")]);
+
+    let items = client.request("textDocument/completion", at(1, 0));
+    assert_eq!(items[0]["label"], "port");
+    assert_eq!(items[0]["insertText"], "port=");
+
+    client.shutdown();
+    std::fs::remove_dir_all(dir).unwrap();
+}
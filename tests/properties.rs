@@ -0,0 +1,135 @@
+//! Checks properties of the parser and the writers on generated input.
+
+use proptest::{
+    collection::{hash_map, vec},
+    prelude::*,
+};
+use slop_rs::{
+    FormatOptions,
+    LineEnding,
+    ParseOptions,
+    Slop,
+    SlopDocument,
+    SlopValue,
+    format,
+};
+
+// Keys and values that may not pass `Slop::validate`.
+fn loose_text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        3 => "[a-z]{0,6}",
+        1 => "[a-z \t\r\n#={}\u{feff}]{0,6}",
+    ]
+}
+
+fn loose_slop() -> impl Strategy<Value = Slop> {
+    let value = prop_oneof![
+        loose_text().prop_map(SlopValue::String),
+        vec(loose_text(), 0..4).prop_map(SlopValue::List),
+    ];
+
+    hash_map(loose_text(), value, 0..6).prop_map(|kvs| {
+        let mut slop = Slop::new();
+
+        for (key, value) in kvs {
+            slop.insert_unchecked(key, value);
+        }
+
+        slop
+    })
+}
+
+// Text that looks like SLOP, but may be invalid.
+fn slop_text() -> impl Strategy<Value = String> {
+    let line = prop_oneof![
+        "[ \t]{0,2}[a-z]{0,3}=[a-z =#{}]{0,4}",
+        "[ \t]{0,2}[a-z]{0,3}\\{",
+        "[ \t]{0,2}[a-z#={} ]{0,6}",
+        Just("}".to_string()),
+        Just("#!include other.slop".to_string()),
+        any::<String>(),
+    ];
+
+    (vec(line, 0..10), prop_oneof![Just("\n"), Just("\r\n")])
+        .prop_map(|(lines, line_ending)| lines.join(line_ending))
+}
+
+fn assert_round_trips(slop: &Slop) -> Result<(), TestCaseError> {
+    let sorted = FormatOptions { sort_keys: true, ..Default::default() };
+    let crlf = FormatOptions { line_ending: LineEnding::CrLf, ..Default::default() };
+
+    prop_assert_eq!(&slop.to_string().parse::<Slop>()?, slop);
+    prop_assert_eq!(&slop.to_string_pretty().parse::<Slop>()?, slop);
+    prop_assert_eq!(&slop.to_string_formatted(&sorted).parse::<Slop>()?, slop);
+    prop_assert_eq!(&slop.to_string_formatted(&crlf).parse::<Slop>()?, slop);
+    prop_assert_eq!(&slop.to_string().parse::<SlopDocument>()?.to_slop(), slop);
+    prop_assert_eq!(&Slop::parse_with(&slop.to_string(), &Default::default())?, slop);
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn generated_slops_are_valid(slop in any::<Slop>()) {
+        prop_assert!(slop.validate().is_ok(), "{:?}", slop.validate());
+    }
+
+    #[test]
+    fn valid_slops_round_trip(slop in any::<Slop>()) {
+        assert_round_trips(&slop)?;
+    }
+
+    #[test]
+    fn validate_guarantees_round_trip(slop in loose_slop()) {
+        if slop.validate().is_ok() {
+            assert_round_trips(&slop)?;
+        }
+    }
+
+    #[test]
+    fn formatting_is_idempotent(text in slop_text(), sort_keys in any::<bool>()) {
+        let options = FormatOptions { sort_keys, ..Default::default() };
+
+        if let Ok(formatted) = format(&text, &options) {
+            prop_assert_eq!(&format(&formatted, &options)?, &formatted);
+
+            if !sort_keys {
+                prop_assert_eq!(formatted.parse::<Slop>()?, text.parse::<Slop>()?);
+            }
+        }
+    }
+
+    #[test]
+    fn parsers_agree(text in slop_text()) {
+        let slop = text.parse::<Slop>().ok();
+
+        prop_assert_eq!(&text.parse::<SlopDocument>().ok().map(|d| d.to_slop()), &slop);
+        prop_assert_eq!(&Slop::parse_with(&text, &Default::default()).ok(), &slop);
+        prop_assert_eq!(&Slop::read_with(text.as_bytes(), &Default::default()).ok(), &slop);
+    }
+
+    #[test]
+    fn parsing_never_panics(text in slop_text()) {
+        let limits = ParseOptions {
+            max_bytes: Some(32),
+            max_key_len: Some(2),
+            max_value_len: Some(2),
+            max_kvs: Some(2),
+            max_list_items: Some(2),
+            lossy: false,
+        };
+
+        let _ = text.parse::<Slop>();
+        let _ = text.parse::<SlopDocument>();
+        let _ = Slop::parse_with(&text, &limits);
+        let _ = Slop::read_with(text.as_bytes(), &limits);
+    }
+
+    #[test]
+    fn decoding_never_panics(bytes in vec(any::<u8>(), 0..64), lossy in any::<bool>()) {
+        let options = ParseOptions { lossy, ..Default::default() };
+
+        let _ = slop_rs::decode(&bytes, lossy);
+        let _ = Slop::from_bytes(&bytes);
+        let _ = Slop::read_with(&bytes[..], &options);
+    }
+}